@@ -0,0 +1,339 @@
+//! Where the time at the table comes from.
+//!
+//! With a `RealClock` philosophers really sleep and really block on each
+//! other, and a run takes as long as its meals. A `VirtualClock` runs the
+//! same threads one at a time instead, as a discrete-event simulation:
+//! sleeping only moves the clock on, the next philosopher to wake up is
+//! always the one due first, and ties are broken by who went to sleep
+//! first. Nothing is left to the operating system's scheduler, so a
+//! seeded run gives the same timeline every time, in no time at all.
+//!
+//! Forks are taken and put back through `take` and `put_back`. Everything
+//! else that waits for somebody (a seat from the waiter, a clean fork)
+//! goes through `block_until`, and everything that could let somebody go
+//! on calls `notify`.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap};
+use std::sync::TryLockError;
+use std::time::{Duration, Instant};
+
+use sync::{thread, Condvar, Mutex, MutexGuard};
+
+/// A clock the whole table shares. `who` is always a philosopher's seat.
+pub trait Clock: Send + Sync {
+    /// Time since the clock was made.
+    fn now(&self) -> Duration;
+
+    /// Lets `duration` pass for `who`.
+    fn sleep(&self, who: usize, duration: Duration);
+
+    /// Returns once `ready` does, asking again after every `notify`.
+    /// `ready` is never asked while another `ready` of the same clock is
+    /// being asked, so it may take what it waits for.
+    fn block_until(&self, who: usize, ready: &mut dyn FnMut() -> bool);
+
+    /// Something that `block_until` waits for may have changed.
+    fn notify(&self);
+
+    /// Locks `fork` for `who`, waiting as long as it takes. A philosopher
+    /// who panicked with the fork in hand can't hurt the fork, so a
+    /// poisoned one is taken all the same.
+    fn take<'a>(&self, who: usize, fork: &'a Mutex<()>) -> MutexGuard<'a, ()>;
+
+    /// Unlocks a fork locked by `take` or `try_take`.
+    fn put_back(&self, guard: MutexGuard<()>);
+
+    /// Locks `fork` if nobody holds it.
+    fn try_take<'a>(&self, fork: &'a Mutex<()>) -> Option<MutexGuard<'a, ()>> {
+        match fork.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    /// `who` sits down. Call before anything else from `who`'s thread.
+    fn arrive(&self, _who: usize) {}
+
+    /// `who` leaves the table. Call last from `who`'s thread.
+    fn leave(&self, _who: usize) {}
+}
+
+/// The time it really is, with threads that really sleep.
+pub struct RealClock {
+    started: Instant,
+    /// Counts notifications; waiters sleep on `changed` in between.
+    changes: Mutex<u64>,
+    changed: Condvar,
+}
+
+impl RealClock {
+    pub fn new() -> RealClock {
+        RealClock { started: Instant::now(), changes: Mutex::new(0), changed: Condvar::new() }
+    }
+}
+
+impl Default for RealClock {
+    fn default() -> RealClock {
+        RealClock::new()
+    }
+}
+
+impl Clock for RealClock {
+    fn now(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    fn sleep(&self, _who: usize, duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    fn block_until(&self, _who: usize, ready: &mut dyn FnMut() -> bool) {
+        // Held while asking, so a `notify` can't slip in between asking
+        // and going to sleep.
+        let mut changes = self.changes.lock().unwrap();
+        while !ready() {
+            changes = self.changed.wait(changes).unwrap();
+        }
+    }
+
+    fn notify(&self) {
+        *self.changes.lock().unwrap() += 1;
+        self.changed.notify_all();
+    }
+
+    // A plain lock: the operating system wakes whoever waits for it.
+    fn take<'a>(&self, _who: usize, fork: &'a Mutex<()>) -> MutexGuard<'a, ()> {
+        fork.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn put_back(&self, guard: MutexGuard<()>) {
+        drop(guard);
+    }
+}
+
+/// Who runs when, under a `VirtualClock`.
+struct Schedule {
+    now: Duration,
+    /// The only philosopher allowed to run. `None` once nobody can.
+    running: Option<usize>,
+    /// Everyone due to run, by when and then by who asked first.
+    due: BinaryHeap<Reverse<(Duration, u64, usize)>>,
+    /// Everyone in `block_until`, waiting for a `notify`.
+    blocked: BTreeSet<usize>,
+    /// Breaks ties between philosophers due at the same time.
+    next_ticket: u64,
+}
+
+impl Schedule {
+    fn wake(&mut self, who: usize, at: Duration) {
+        self.due.push(Reverse((at, self.next_ticket, who)));
+        self.next_ticket += 1;
+    }
+
+    /// Hands the table to whoever is due first, moving the clock on to
+    /// their time.
+    fn run_next(&mut self) {
+        self.running = self.due.pop().map(|Reverse((at, _, who))| {
+            self.now = self.now.max(at);
+            who
+        });
+    }
+}
+
+/// Simulated time: threads take turns, and sleeping costs nothing.
+pub struct VirtualClock {
+    schedule: Mutex<Schedule>,
+    turn: Condvar,
+}
+
+impl VirtualClock {
+    /// A clock for philosophers `0..philosophers`. They start at time
+    /// zero, in order of their seats, and every one of them must
+    /// `arrive` and eventually `leave`.
+    pub fn new(philosophers: usize) -> VirtualClock {
+        let mut schedule = Schedule {
+            now: Duration::default(),
+            running: None,
+            due: BinaryHeap::new(),
+            blocked: BTreeSet::new(),
+            next_ticket: 0,
+        };
+        for who in 0..philosophers {
+            schedule.wake(who, Duration::default());
+        }
+        schedule.run_next();
+        VirtualClock { schedule: Mutex::new(schedule), turn: Condvar::new() }
+    }
+
+    /// Waits until it is `who`'s turn.
+    fn wait_turn<'a>(&'a self, who: usize, mut schedule: MutexGuard<'a, Schedule>) {
+        while schedule.running != Some(who) {
+            schedule = self.turn.wait(schedule).unwrap();
+        }
+    }
+
+    /// Gives the turn away and waits to get it back.
+    fn yield_turn(&self, who: usize, mut schedule: MutexGuard<Schedule>) {
+        schedule.run_next();
+        self.turn.notify_all();
+        self.wait_turn(who, schedule);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        self.schedule.lock().unwrap().now
+    }
+
+    fn sleep(&self, who: usize, duration: Duration) {
+        let mut schedule = self.schedule.lock().unwrap();
+        let at = schedule.now + duration;
+        schedule.wake(who, at);
+        self.yield_turn(who, schedule);
+    }
+
+    fn block_until(&self, who: usize, ready: &mut dyn FnMut() -> bool) {
+        // Only the running philosopher gets here, so nobody can change
+        // anything between `ready` and being marked blocked.
+        while !ready() {
+            let mut schedule = self.schedule.lock().unwrap();
+            schedule.blocked.insert(who);
+            self.yield_turn(who, schedule);
+        }
+    }
+
+    fn notify(&self) {
+        let mut schedule = self.schedule.lock().unwrap();
+        let now = schedule.now;
+        for who in ::std::mem::take(&mut schedule.blocked) {
+            schedule.wake(who, now);
+        }
+    }
+
+    // Blocking on the lock itself would block everybody, as only one
+    // philosopher runs at a time. Instead wait for the turn to come back
+    // after somebody puts a fork down.
+    fn take<'a>(&self, who: usize, fork: &'a Mutex<()>) -> MutexGuard<'a, ()> {
+        let mut guard = None;
+        self.block_until(who, &mut || {
+            guard = self.try_take(fork);
+            guard.is_some()
+        });
+        guard.unwrap()
+    }
+
+    fn put_back(&self, guard: MutexGuard<()>) {
+        drop(guard);
+        self.notify();
+    }
+
+    fn arrive(&self, who: usize) {
+        self.wait_turn(who, self.schedule.lock().unwrap());
+    }
+
+    fn leave(&self, _who: usize) {
+        let mut schedule = self.schedule.lock().unwrap();
+        schedule.run_next();
+        self.turn.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Runs `body` for philosophers `0..count` on threads of their own
+    /// under `clock`, and returns what they logged, in order.
+    fn simulate<F>(clock: Arc<VirtualClock>, count: usize, body: F) -> Vec<(Duration, usize, &'static str)>
+        where F: Fn(usize, &VirtualClock, &dyn Fn(&'static str)) + Send + Sync + 'static
+    {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let body = Arc::new(body);
+        let handles: Vec<_> = (0..count).map(|who| {
+            let (clock, log, body) = (clock.clone(), log.clone(), body.clone());
+            thread::spawn(move || {
+                clock.arrive(who);
+                body(who, &clock, &|what| log.lock().unwrap().push((clock.now(), who, what)));
+                clock.leave(who);
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let log = log.lock().unwrap().clone();
+        log
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn sleeping_is_free_and_in_order() {
+        let started = Instant::now();
+        let clock = Arc::new(VirtualClock::new(3));
+        let log = simulate(clock.clone(), 3, |who, clock, log| {
+            log("up");
+            clock.sleep(who, ms(1000 * (3 - who as u64)));
+            log("awake");
+        });
+        assert!(started.elapsed() < ms(1000));
+        assert_eq!(vec![(ms(0), 0, "up"), (ms(0), 1, "up"), (ms(0), 2, "up"),
+                        (ms(1000), 2, "awake"), (ms(2000), 1, "awake"), (ms(3000), 0, "awake")],
+                   log);
+        assert_eq!(ms(3000), clock.now());
+    }
+
+    #[test]
+    fn ties_go_to_whoever_slept_first() {
+        let log = simulate(Arc::new(VirtualClock::new(2)), 2, |who, clock, log| {
+            // 1 goes to sleep after 0, for as long, so wakes after 0.
+            clock.sleep(who, ms(5));
+            log("awake");
+            clock.sleep(who, ms(if who == 0 { 10 } else { 5 }));
+            log("again");
+        });
+        assert_eq!(vec![(ms(5), 0, "awake"), (ms(5), 1, "awake"),
+                        (ms(10), 1, "again"), (ms(15), 0, "again")],
+                   log);
+    }
+
+    #[test]
+    fn blocked_philosophers_wait_for_notify() {
+        let flag = Arc::new(Mutex::new(false));
+        let log = simulate(Arc::new(VirtualClock::new(2)), 2, move |who, clock, log| {
+            if who == 0 {
+                clock.block_until(0, &mut || *flag.lock().unwrap());
+                log("let through");
+            } else {
+                clock.sleep(1, ms(30));
+                *flag.lock().unwrap() = true;
+                clock.notify();
+                log("opened");
+                clock.sleep(1, ms(1));
+            }
+        });
+        assert_eq!(vec![(ms(30), 1, "opened"), (ms(30), 0, "let through")], log);
+    }
+
+    #[test]
+    fn real_clock_blocks_until_ready() {
+        let clock = Arc::new(RealClock::new());
+        let flag = Arc::new(Mutex::new(false));
+        let opener = {
+            let (clock, flag) = (clock.clone(), flag.clone());
+            thread::spawn(move || {
+                clock.sleep(1, ms(10));
+                *flag.lock().unwrap() = true;
+                clock.notify();
+            })
+        };
+        clock.block_until(0, &mut || *flag.lock().unwrap());
+        assert!(clock.now() >= ms(10));
+        opener.join().unwrap();
+    }
+}
@@ -0,0 +1,282 @@
+//! Forks that keep track of who holds them and who waits for them.
+//!
+//! A `Fork` is a mutex that reports to the table's `WaitGraph`: the
+//! philosopher it is held by, and the philosophers blocked trying to take
+//! it. If philosopher A waits for a fork held by B, and B waits for one
+//! held by A (or so on around any circle), none of them can ever go on.
+//! `WaitGraph::cycle` finds such circles.
+//!
+//! Forks are locked and unlocked by the table's `Clock`, so that under
+//! virtual time a philosopher waiting for a fork lets the others run. A
+//! fork can also report to a `Trace`, for a timeline of who held it.
+
+use std::fmt;
+use std::sync::{self as bookkeeping, Arc};
+
+use clock::Clock;
+use sync::{Mutex, MutexGuard};
+use trace::Trace;
+
+/// The fork each philosopher holds or waits for.
+struct Edges {
+    /// Who holds each fork.
+    holder: Vec<Option<usize>>,
+    /// Which fork each philosopher is blocked on.
+    waiting: Vec<Option<usize>>,
+}
+
+/// Who holds and who waits for which fork, for the whole table.
+pub struct WaitGraph {
+    // Never held while taking another lock, so it can't change who waits
+    // for whom, and the model checker doesn't need to know about it.
+    edges: bookkeeping::Mutex<Edges>,
+}
+
+/// A circle of philosophers each waiting for the next one's fork.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    /// Each philosopher and the fork they wait for, which the next one
+    /// holds. The last one waits for the first.
+    pub links: Vec<(usize, usize)>,
+}
+
+impl Cycle {
+    /// The cycle with the philosophers' names, such as "Judith Butler →
+    /// fork 1 → Gilles Deleuze → fork 0 → Judith Butler".
+    pub fn describe<S: AsRef<str>>(&self, names: &[S]) -> String {
+        let mut text = String::new();
+        for &(philosopher, fork) in &self.links {
+            text.push_str(&format!("{} → fork {} → ", names[philosopher].as_ref(), fork));
+        }
+        text.push_str(names[self.links[0].0].as_ref());
+        text
+    }
+}
+
+impl fmt::Display for Cycle {
+    /// The cycle with philosophers numbered by their seat.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<String> = (0..=self.links.iter().map(|l| l.0).max().unwrap_or(0))
+            .map(|i| format!("philosopher {}", i))
+            .collect();
+        f.write_str(&self.describe(&names))
+    }
+}
+
+impl WaitGraph {
+    pub fn new(forks: usize, philosophers: usize) -> WaitGraph {
+        WaitGraph {
+            edges: bookkeeping::Mutex::new(Edges {
+                holder: vec![None; forks],
+                waiting: vec![None; philosophers],
+            }),
+        }
+    }
+
+    /// Who holds `fork`.
+    pub fn holder(&self, fork: usize) -> Option<usize> {
+        self.edges.lock().unwrap().holder[fork]
+    }
+
+    /// Which fork `philosopher` is blocked on.
+    pub fn waiting(&self, philosopher: usize) -> Option<usize> {
+        self.edges.lock().unwrap().waiting[philosopher]
+    }
+
+    /// A circle of philosophers waiting for each other, if there is one.
+    /// It starts with the lowest numbered philosopher in it.
+    pub fn cycle(&self) -> Option<Cycle> {
+        let edges = self.edges.lock().unwrap();
+        // Everybody waits for at most one fork and every fork has at most
+        // one holder, so from any philosopher there is one path to follow.
+        for start in 0..edges.waiting.len() {
+            let mut path: Vec<(usize, usize)> = Vec::new();
+            let mut current = start;
+            while let Some(fork) = edges.waiting[current] {
+                if let Some(i) = path.iter().position(|&(p, _)| p == current) {
+                    let mut links = path.split_off(i);
+                    let lowest = links.iter().enumerate().min_by_key(|&(_, l)| l.0).unwrap().0;
+                    links.rotate_left(lowest);
+                    return Some(Cycle { links });
+                }
+                path.push((current, fork));
+                current = match edges.holder[fork] {
+                    Some(holder) => holder,
+                    None => break,
+                };
+            }
+        }
+        None
+    }
+}
+
+/// A fork: a mutex that tells the `WaitGraph` who holds it.
+pub struct Fork {
+    number: usize,
+    lock: Mutex<()>,
+    graph: Arc<WaitGraph>,
+    clock: Arc<dyn Clock>,
+    trace: Option<Arc<Trace>>,
+}
+
+/// A fork in a philosopher's hand. Dropping it puts the fork down.
+pub struct ForkGuard<'a> {
+    fork: &'a Fork,
+    philosopher: usize,
+    // Only taken out by `drop` below, once the graph no longer shows the
+    // fork as held, so it never shows a fork as free while it is locked.
+    guard: Option<MutexGuard<'a, ()>>,
+}
+
+impl Fork {
+    pub fn new(number: usize, graph: Arc<WaitGraph>, clock: Arc<dyn Clock>) -> Fork {
+        Fork { number, lock: Mutex::new(()), graph, clock, trace: None }
+    }
+
+    /// The same fork, also telling `trace` whenever it is picked up and
+    /// released.
+    pub fn with_trace(self, trace: Arc<Trace>) -> Fork {
+        Fork { trace: Some(trace), ..self }
+    }
+
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    /// Takes the fork for `philosopher`, waiting until it is free.
+    pub fn lock(&self, philosopher: usize) -> ForkGuard<'_> {
+        self.graph.edges.lock().unwrap().waiting[philosopher] = Some(self.number);
+        let guard = self.clock.take(philosopher, &self.lock);
+        self.taken(philosopher, guard)
+    }
+
+    /// Takes the fork for `philosopher` if nobody holds it.
+    pub fn try_lock(&self, philosopher: usize) -> Option<ForkGuard<'_>> {
+        self.clock.try_take(&self.lock).map(|guard| self.taken(philosopher, guard))
+    }
+
+    fn taken<'a>(&'a self, philosopher: usize, guard: MutexGuard<'a, ()>) -> ForkGuard<'a> {
+        {
+            let mut edges = self.graph.edges.lock().unwrap();
+            edges.waiting[philosopher] = None;
+            edges.holder[self.number] = Some(philosopher);
+        }
+        if let Some(ref trace) = self.trace {
+            trace.picked_up(philosopher, self.number);
+        }
+        ForkGuard { fork: self, philosopher, guard: Some(guard) }
+    }
+}
+
+impl Drop for ForkGuard<'_> {
+    fn drop(&mut self) {
+        // loom ends a deadlocked model by unwinding its threads, after
+        // which none of its locks may be touched again, this one included.
+        #[cfg(loom)]
+        {
+            if ::std::thread::panicking() {
+                ::std::mem::forget(self.guard.take());
+                return;
+            }
+        }
+        if let Some(ref trace) = self.fork.trace {
+            trace.released(self.philosopher, self.fork.number);
+        }
+        self.fork.graph.edges.lock().unwrap().holder[self.fork.number] = None;
+        if let Some(guard) = self.guard.take() {
+            self.fork.clock.put_back(guard);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::RealClock;
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Duration;
+
+    fn forks(count: usize) -> (Arc<WaitGraph>, Vec<Fork>) {
+        let graph = Arc::new(WaitGraph::new(count, count));
+        let clock: Arc<dyn Clock> = Arc::new(RealClock::new());
+        let forks = (0..count).map(|i| Fork::new(i, graph.clone(), clock.clone())).collect();
+        (graph, forks)
+    }
+
+    #[test]
+    fn holders_are_tracked() {
+        let (graph, forks) = forks(2);
+        let guard = forks[1].lock(0);
+        assert_eq!(Some(0), graph.holder(1));
+        assert!(forks[1].try_lock(1).is_none());
+        assert_eq!(None, graph.waiting(1));
+        drop(guard);
+        assert_eq!(None, graph.holder(1));
+        assert!(forks[1].try_lock(1).is_some());
+    }
+
+    #[test]
+    fn traced_forks() {
+        let clock: Arc<dyn Clock> = Arc::new(RealClock::new());
+        let trace = Arc::new(Trace::new(&["a", "b"], 1, clock.clone()));
+        let fork = Fork::new(0, Arc::new(WaitGraph::new(1, 2)), clock).with_trace(trace.clone());
+        drop(fork.lock(1));
+        let marks: Vec<String> = trace.events().into_iter()
+            .filter(|e| e.ph == "i")
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(vec!["picked up fork 0", "released fork 0"], marks);
+    }
+
+    #[test]
+    fn no_cycle_without_waiting() {
+        let (graph, forks) = forks(3);
+        let _a = forks[0].lock(0);
+        let _b = forks[1].lock(1);
+        assert_eq!(None, graph.cycle());
+    }
+
+    #[test]
+    fn a_real_deadlock_is_found() {
+        let (graph, forks) = forks(3);
+        let forks = Arc::new(forks);
+        let barrier = Arc::new(Barrier::new(3));
+
+        // Everyone takes their left fork, waits for the others to do the
+        // same, and reaches for the right one. These threads never end.
+        for me in 0..3 {
+            let (forks, barrier) = (forks.clone(), barrier.clone());
+            thread::spawn(move || {
+                let _left = forks[me].lock(me);
+                barrier.wait();
+                let _right = forks[(me + 1) % 3].lock(me);
+            });
+        }
+
+        let cycle = loop {
+            if let Some(cycle) = graph.cycle() {
+                break cycle;
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(vec![(0, 1), (1, 2), (2, 0)], cycle.links);
+        assert_eq!("a → fork 1 → b → fork 2 → c → fork 0 → a", cycle.describe(&["a", "b", "c"]));
+    }
+
+    #[test]
+    fn cycles_start_with_the_lowest_philosopher() {
+        let (graph, _) = forks(4);
+        {
+            let mut edges = graph.edges.lock().unwrap();
+            // 3 waits for 2's fork and 2 for 3's; 0 waits for 3 but is
+            // not part of the circle.
+            edges.holder = vec![None, None, Some(2), Some(3)];
+            edges.waiting = vec![Some(3), None, Some(3), Some(2)];
+        }
+        let cycle = graph.cycle().unwrap();
+        assert_eq!(vec![(2, 3), (3, 2)], cycle.links);
+        assert_eq!("philosopher 2 → fork 3 → philosopher 3 → fork 2 → philosopher 2",
+                   cycle.to_string());
+    }
+}
@@ -0,0 +1,41 @@
+//! The Dining Philosophers: the classic concurrency problem.
+//!
+//! Philosophers sit around a round table with one fork between each pair
+//! of neighbours, and need both forks next to them to eat. The table and
+//! the seating live here; the binary in `main.rs` reads the command line
+//! and runs one thread per philosopher, each thinking and eating as their
+//! `Lifecycle` says. How the forks are picked up is up to a
+//! `ForkStrategy`, and the `monitor` can watch for philosophers waiting
+//! on each other in a circle. The `Report` at the end says how fairly it
+//! all went, and a `Trace` keeps the whole timeline. Time itself comes
+//! from a `Clock`: the real one, or a virtual one that runs instantly and
+//! always the same way. The locks come from `sync`, so that loom can
+//! check every order in which the philosophers may take them.
+
+#[cfg(loom)]
+extern crate loom;
+extern crate rand;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+
+pub mod clock;
+pub mod fork;
+pub mod lifecycle;
+pub mod monitor;
+pub mod options;
+pub mod report;
+pub mod strategy;
+pub mod sync;
+pub mod table;
+pub mod trace;
+
+pub use clock::{Clock, RealClock, VirtualClock};
+pub use fork::{Cycle, Fork, WaitGraph};
+pub use lifecycle::{Distribution, Lifecycle, Stop};
+pub use report::Report;
+pub use strategy::{ForkStrategy, Strategy};
+pub use table::{Philosopher, Table};
+pub use trace::Trace;
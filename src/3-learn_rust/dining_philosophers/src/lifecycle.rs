@@ -0,0 +1,244 @@
+//! How a philosopher spends the evening: think, get hungry, eat, and
+//! again, until enough meals are eaten or time is up.
+//!
+//! How long thinking and eating take is drawn anew every time, from a
+//! `Distribution` given on the command line, so neighbours get hungry at
+//! different moments and really compete for their forks. Given a seed,
+//! every philosopher draws the same durations on every run.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng, StdRng};
+use rand::distributions::{Exp, IndependentSample, Normal};
+
+/// Where the lengths of thinking or eating are drawn from. Everything is
+/// in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distribution {
+    /// Always the same: `fixed:200`.
+    Fixed(u64),
+    /// Anything from the first to the second, both included:
+    /// `uniform:100-300`.
+    Uniform(u64, u64),
+    /// Mostly short, now and then long, with the given mean: `exp:200`.
+    Exponential(u64),
+    /// Around a mean with a standard deviation, never below zero:
+    /// `normal:200,50`.
+    Normal(u64, u64),
+}
+
+impl Distribution {
+    /// Draws one duration.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Duration {
+        let millis = match *self {
+            Distribution::Fixed(millis) => millis as f64,
+            Distribution::Uniform(low, high) => uniform(rng, low, high) as f64,
+            Distribution::Exponential(0) => 0.0,
+            Distribution::Exponential(mean) => Exp::new(1.0 / mean as f64).ind_sample(rng),
+            Distribution::Normal(mean, deviation) => {
+                Normal::new(mean as f64, deviation as f64).ind_sample(rng).max(0.0)
+            }
+        };
+        Duration::from_micros((millis * 1000.0) as u64)
+    }
+}
+
+/// A number from `low` to `high`, both included. `gen_range` leaves out
+/// its upper end, which can't be one past `u64::MAX`.
+fn uniform<R: Rng>(rng: &mut R, low: u64, high: u64) -> u64 {
+    match (low, high) {
+        (0, u64::MAX) => rng.gen(),
+        (low, u64::MAX) => rng.gen_range(low - 1, high) + 1,
+        (low, high) => rng.gen_range(low, high + 1),
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Distribution::Fixed(millis) => write!(f, "fixed:{}", millis),
+            Distribution::Uniform(low, high) => write!(f, "uniform:{}-{}", low, high),
+            Distribution::Exponential(mean) => write!(f, "exp:{}", mean),
+            Distribution::Normal(mean, deviation) => write!(f, "normal:{},{}", mean, deviation),
+        }
+    }
+}
+
+impl FromStr for Distribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Distribution, String> {
+        let bad = || format!("bad distribution '{}', expected fixed:MS, uniform:MS-MS, \
+                              exp:MS or normal:MS,MS", s);
+        let millis = |text: &str| text.trim().parse::<u64>().map_err(|_| bad());
+        let two = |text: &str, separator: char| match text.find(separator) {
+            Some(i) => Ok((millis(&text[..i])?, millis(&text[i + 1..])?)),
+            None => Err(bad()),
+        };
+
+        let (kind, args) = match s.find(':') {
+            Some(i) => (s[..i].trim(), &s[i + 1..]),
+            None => return Err(bad()),
+        };
+        match &kind.to_ascii_lowercase()[..] {
+            "fixed" => Ok(Distribution::Fixed(millis(args)?)),
+            "uniform" => {
+                let (low, high) = two(args, '-')?;
+                if low > high {
+                    return Err(format!("'{}' goes from high to low", s));
+                }
+                Ok(Distribution::Uniform(low, high))
+            }
+            "exp" => Ok(Distribution::Exponential(millis(args)?)),
+            "normal" => {
+                let (mean, deviation) = two(args, ',')?;
+                Ok(Distribution::Normal(mean, deviation))
+            }
+            _ => Err(bad()),
+        }
+    }
+}
+
+/// When a philosopher leaves the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// After this many meals.
+    Meals(usize),
+    /// Once this long has passed. A philosopher who is already hungry
+    /// still gets their meal.
+    After(Duration),
+}
+
+/// The evening of every philosopher at the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lifecycle {
+    pub think: Distribution,
+    pub eat: Distribution,
+    pub stop: Stop,
+    /// Whether philosophers say out loud what they are doing.
+    pub narrate: bool,
+    /// Where the random durations come from. `None` is different every
+    /// run.
+    pub seed: Option<u64>,
+}
+
+/// Meals each philosopher has when nothing else is asked for.
+pub const DEFAULT_MEALS: usize = 3;
+
+impl Default for Lifecycle {
+    fn default() -> Lifecycle {
+        Lifecycle {
+            think: Distribution::Uniform(0, 200),
+            eat: Distribution::Uniform(100, 300),
+            stop: Stop::Meals(DEFAULT_MEALS),
+            narrate: true,
+            seed: None,
+        }
+    }
+}
+
+/// Random numbers for one of several independent `stream`s, such as one
+/// per philosopher. The same seed and stream always give the same numbers.
+pub fn rng(seed: Option<u64>, stream: usize) -> StdRng {
+    match seed {
+        Some(seed) => SeedableRng::from_seed(&[seed as usize, stream][..]),
+        None => StdRng::new().expect("the operating system has no random numbers"),
+    }
+}
+
+impl Lifecycle {
+    /// Whether a philosopher who has eaten `meals` times, `elapsed` after
+    /// sitting down, may go on.
+    pub fn goes_on(&self, meals: usize, elapsed: Duration) -> bool {
+        match self.stop {
+            Stop::Meals(wanted) => meals < wanted,
+            Stop::After(limit) => elapsed < limit,
+        }
+    }
+
+    /// How long a philosopher `elapsed` after sitting down thinks, given
+    /// they would like to think for `wanted`. Nobody thinks past the end
+    /// of the evening.
+    pub fn thinking_time(&self, wanted: Duration, elapsed: Duration) -> Duration {
+        match self.stop {
+            Stop::Meals(_) => wanted,
+            Stop::After(limit) => wanted.min(limit.checked_sub(elapsed).unwrap_or_default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+
+    #[test]
+    fn distributions_round_trip() {
+        for text in &["fixed:200", "uniform:100-300", "exp:50", "normal:200,50"] {
+            assert_eq!(text.to_string(), text.parse::<Distribution>().unwrap().to_string());
+        }
+        assert_eq!(Ok(Distribution::Uniform(1, 2)), "Uniform: 1 - 2".parse());
+    }
+
+    #[test]
+    fn bad_distributions() {
+        for text in &["200", "fixed:", "fixed:soon", "uniform:100", "uniform:3-1", "normal:1",
+                      "poisson:3"] {
+            assert!(text.parse::<Distribution>().is_err(), "{}", text);
+        }
+        assert_eq!(Err("'uniform:3-1' goes from high to low".to_string()),
+                   "uniform:3-1".parse::<Distribution>());
+    }
+
+    #[test]
+    fn samples_stay_in_range() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            assert_eq!(Duration::from_millis(7), Distribution::Fixed(7).sample(&mut rng));
+            let uniform = Distribution::Uniform(10, 20).sample(&mut rng);
+            assert!(uniform >= Duration::from_millis(10) && uniform <= Duration::from_millis(20));
+            // Negative draws are cut off at zero rather than panicking.
+            Distribution::Normal(1, 100).sample(&mut rng);
+            Distribution::Exponential(0).sample(&mut rng);
+        }
+    }
+
+    #[test]
+    fn uniform_up_to_the_largest_number() {
+        let mut rng = rand::thread_rng();
+        let max = u64::MAX;
+        assert_eq!(Ok(Distribution::Uniform(0, max)), format!("uniform:0-{}", max).parse());
+        for _ in 0..1000 {
+            Distribution::Uniform(0, max).sample(&mut rng);
+            assert!(uniform(&mut rng, max - 1, max) >= max - 1);
+            assert_eq!(max, uniform(&mut rng, max, max));
+        }
+    }
+
+    #[test]
+    fn seeded_streams() {
+        let draws = |seed, stream| -> Vec<Duration> {
+            let mut rng = rng(Some(seed), stream);
+            (0..5).map(|_| Distribution::Uniform(0, 1_000_000).sample(&mut rng)).collect()
+        };
+        assert_eq!(draws(7, 0), draws(7, 0));
+        assert!(draws(7, 0) != draws(7, 1));
+        assert!(draws(7, 0) != draws(8, 0));
+    }
+
+    #[test]
+    fn stopping() {
+        let meals = Lifecycle { stop: Stop::Meals(2), ..Lifecycle::default() };
+        assert!(meals.goes_on(1, Duration::from_secs(100)));
+        assert!(!meals.goes_on(2, Duration::from_secs(0)));
+
+        let second = Duration::from_secs(1);
+        let timed = Lifecycle { stop: Stop::After(second), ..Lifecycle::default() };
+        assert!(timed.goes_on(100, second / 2));
+        assert!(!timed.goes_on(0, second));
+        assert_eq!(second / 4, timed.thinking_time(second, second * 3 / 4));
+        assert_eq!(Duration::from_secs(0), timed.thinking_time(second, second * 2));
+    }
+}
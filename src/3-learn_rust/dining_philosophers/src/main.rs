@@ -3,90 +3,128 @@
  * The classic concurrency problem.
  */
 
-use std::thread;
-use std::sync::{Mutex, Arc};
-
-struct Philosopher {
-    name: String,
-    left: usize, // Vector index of left fork.
-    right: usize, // Vector index of right fork.
-}
+// The table and the philosophers live in our own library crate (src/lib.rs).
+extern crate dining_philosophers;
 
-impl Philosopher {
-    fn new(name: &str, left: usize, right: usize) -> Philosopher {
-        Philosopher {
-            name: name.to_string(),
-            left: left,
-            right: right,
-        }
-    }
-
-    fn eat(&self, table: &Table) {
-        // Get access to the Mutex of the forks to the left and right
-        // of the philosopher, if they are available. By calling lock,
-        // we prevent any one else from accessing that Mutex.
-        // If someone else is using the Mutex, a thread panic will
-        // occur. We don't want this to happen, so we call unwrap().
-        // We prepend underscore to bindings that we know we won't use.
-        // That way Rust won't warn us about an unused binding.
-        let _left = table.forks[self.left].lock().unwrap();
-        let _right = table.forks[self.right].lock().unwrap();
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+use std::thread;
 
-        println!("{} is eating.", self.name);
+use dining_philosophers::{monitor, Clock, ForkStrategy, Philosopher, RealClock, Report, Table, Trace,
+                          VirtualClock};
+use dining_philosophers::options::{self, Options, USAGE};
+use dining_philosophers::report::Format;
+use dining_philosophers::strategy::Naive;
+use dining_philosophers::table::{self, DEFAULT_NAMES};
 
-        thread::sleep_ms(1000);
+// Exit statuses, so scripts can tell what went wrong.
+const EXIT_USAGE: i32 = 2;
+const EXIT_DEADLOCK: i32 = 3;
 
-        println!("{} is done eating.", self.name);
+fn main() {
+    // Skip the first argument, it is the program name.
+    let options = match options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            println!("error: {}\n\n{}", message, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return;
     }
-}
 
-struct Table {
-    // A vector of Mutex's. Mutex's are used to control concurrency.
-    // Only one thread can access the contents at once, which is the
-    // exact requirement with our forks. () indicates an empty tuple,
-    // since we don't need the value, we just need to hold onto it.
-    forks: Vec<Mutex<()>>,
-}
-
-fn main() {
-    // 5th Iteration: Final version!
+    let names = names(&options);
 
     // Table is an "atomic reference count". We need to share Table
     // across multiple threads. As we share it, the reference count
     // increase, and when each thread ends, it will decrease.
-    let table = Arc::new(Table { forks: vec![
-        Mutex::new(()),
-        Mutex::new(()),
-        Mutex::new(()),
-        Mutex::new(()),
-        Mutex::new(()),
-    ]});
+    // In virtual time the philosophers take turns and only pretend to
+    // sleep, so the run is over at once and the same for the same seed.
+    let clock: Arc<dyn Clock> = if options.virtual_time {
+        Arc::new(VirtualClock::new(names.len()))
+    } else {
+        Arc::new(RealClock::new())
+    };
+    let mut table = Table::with_clock(names.len(), clock.clone());
+    if options.trace.is_some() {
+        table = table.with_trace(Arc::new(Trace::new(&names, names.len(), clock.clone())));
+    }
+    let table = Arc::new(table);
+
+    // Everyone gets the forks on either side of their seat. How they
+    // pick them up is the same for the whole table.
+    let philosophers = Philosopher::seat(&names);
+    let strategy: Arc<dyn ForkStrategy> = match options.naive_pause {
+        Some(pause) => Arc::new(Naive::pausing(pause)),
+        None => Arc::from(options.strategy.build(names.len(), options.lifecycle.seed)),
+    };
+    let lifecycle = options.lifecycle;
+    let started = clock.now();
 
-    let philosophers = vec![
-        Philosopher::new("Judith Butler", 0, 1),
-        Philosopher::new("Gilles Deleuze", 1, 2),
-        Philosopher::new("Karl Marx", 2, 3),
-        Philosopher::new("Emma Goldman", 3, 4),
-        Philosopher::new("Michel Foucault", 0, 4), // We do 0, 4 to prevent a deadlock,
-        // which is one way to solve the problem.
-    ];
+    // The monitor gets a thread of its own. It only ever returns on a
+    // deadlock; otherwise it goes away when main does.
+    if let Some(every) = options.monitor {
+        let table = table.clone();
+        let names = names.clone();
+        let trace_file = options.trace.clone();
+        thread::spawn(move || {
+            let cycle = monitor::watch(table.graph(), every);
+            println!("Deadlock: {}", cycle.describe(&names));
+            // The timeline up to the deadlock shows how it came about.
+            save_trace(&table, &trace_file);
+            process::exit(EXIT_DEADLOCK);
+        });
+    }
 
     // Create handles to threads.
     let handles: Vec<_> = philosophers.into_iter().map(|p| {
         let table = table.clone(); // increase reference count.
+        let strategy = strategy.clone();
         // reference count will automatically decrease when it
         // falls out of scope. That way, we can keep track of
         // the nuber of references to table exist across our
         // threads. Otherwise, we wouldn't know how to
         // deallocate it.
         thread::spawn(move || {
-            p.eat(&table);
+            p.dine(&table, &*strategy, &lifecycle)
         })
     }).collect();
 
-    for h in handles {
-        // Block execution until the thread has completed execution.
-        // Thread will complete their work before program exits.
-        h.join().unwrap();
+    // Block execution until every thread has completed execution, and
+    // keep how long each philosopher waited for their forks.
+    let waits: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    save_trace(&table, &options.trace);
+
+    let report = Report::new(options.strategy, &names, &waits, clock.now() - started, options.starving);
+    match options.report {
+        Format::Table => print!("\n{}", report),
+        Format::Json => println!("{}", report.to_json()),
     }
 }
+
+/// Writes the table's trace to `path`, if it keeps one.
+fn save_trace(table: &Table, path: &Option<PathBuf>) {
+    if let (Some(trace), Some(path)) = (table.trace(), path.as_ref()) {
+        if let Err(err) = trace.save(path) {
+            println!("error: could not write the trace to {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// The names of everyone at the table, as asked for on the command line.
+fn names(options: &Options) -> Vec<String> {
+    let names = match options.names_file {
+        Some(ref path) => table::names_from_file(path).unwrap_or_else(|err| {
+            println!("error: {}", err);
+            process::exit(EXIT_USAGE);
+        }),
+        None => DEFAULT_NAMES.iter().map(|name| name.to_string()).collect(),
+    };
+    let count = options.count.unwrap_or(names.len());
+    table::pick_names(&names, count)
+}
@@ -0,0 +1,22 @@
+//! Watching the table for deadlocks while it eats.
+
+use std::thread;
+use std::time::Duration;
+
+use fork::{Cycle, WaitGraph};
+
+/// Checks `graph` every `every` until its philosophers wait for each
+/// other in a circle, and returns that circle. Never returns on a table
+/// that can't deadlock, so run it on a thread of its own.
+///
+/// Only blocking `Fork::lock` calls count as waiting, so a cycle is a
+/// deadlock for good, not a moment of bad luck: every philosopher in it
+/// holds a fork and won't let go before getting the next one.
+pub fn watch(graph: &WaitGraph, every: Duration) -> Cycle {
+    loop {
+        if let Some(cycle) = graph.cycle() {
+            return cycle;
+        }
+        thread::sleep(every);
+    }
+}
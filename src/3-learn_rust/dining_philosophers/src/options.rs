@@ -0,0 +1,331 @@
+//! Command line options for the dining philosophers binary.
+//!
+//! Parsed by hand, like the guessing game does, instead of pulling in an
+//! argument parsing crate.
+
+use std::path::PathBuf;
+use std::time::Duration;
+use std::str::FromStr;
+
+use lifecycle::{Lifecycle, Stop};
+use report::{self, Format};
+use strategy::Strategy;
+use table::MIN_PHILOSOPHERS;
+
+/// Printed for `--help` and after a bad command line.
+pub const USAGE: &str = "\
+Usage: dining_philosophers [OPTIONS]
+
+Options:
+    --count N           how many philosophers sit at the table, at least 2
+                        (default: one per name)
+    --names-file F      take the names from file F, one per line (default:
+                        five famous philosophers). Missing names are made
+                        up, extra ones stay away
+    --strategy NAME     how forks are picked up (default ordered):
+                          ordered        lower numbered fork first
+                          waiter         at most N - 1 reach for forks
+                          backoff        put the first fork back and retry
+                                         after a random pause
+                          chandy-misra   clean and dirty forks
+                          naive          left, then right; can deadlock
+    --naive-pause MS    with --strategy naive, wait MS milliseconds between
+                        the left fork and the right one, so the deadlock
+                        is all but certain
+    --meals N           meals each philosopher eats (default 3)
+    --run-time MS       eat and think for MS milliseconds instead of a
+                        number of meals
+    --think DIST        how long thinking takes (default uniform:0-200)
+    --eat DIST          how long eating takes (default uniform:100-300)
+    --virtual           simulate the time instead of waiting for it: the
+                        run is over at once, and the same every time for
+                        the same --seed
+    --seed N            draw the random durations from seed N, so they
+                        are the same every run
+    --monitor MS        look for philosophers waiting on each other in a
+                        circle every MS milliseconds, and stop if they do
+    --report FORMAT     how the report at the end is printed: table, or
+                        json for scripts, which also keeps the
+                        philosophers quiet (default table)
+    --starving MS       raise the starvation alarm for anybody who waited
+                        longer than MS milliseconds for forks (default 1000)
+    --trace F           write the whole timeline to F in the Chrome trace
+                        format, to open in chrome://tracing or Perfetto
+    -h, --help          print this message
+
+Durations DIST are in milliseconds, drawn anew every time from one of:
+    fixed:MS            always MS
+    uniform:LOW-HIGH    anything from LOW to HIGH
+    exp:MEAN            exponential, mostly short and now and then long
+    normal:MEAN,DEV     normal, never below zero
+
+Exit status:
+    0   everyone ate
+    2   bad command line or names file
+    3   --monitor found a deadlock";
+
+/// Everything the command line can ask for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Philosophers at the table. `None` seats everyone named.
+    pub count: Option<usize>,
+    /// Where the names come from. `None` uses `table::DEFAULT_NAMES`.
+    pub names_file: Option<PathBuf>,
+    /// How forks are picked up.
+    pub strategy: Strategy,
+    /// How long `Naive` waits between its forks. `None` doesn't wait.
+    pub naive_pause: Option<Duration>,
+    /// How long everybody thinks and eats, and for how long.
+    pub lifecycle: Lifecycle,
+    /// Run in virtual time rather than real time.
+    pub virtual_time: bool,
+    /// How often to look for a deadlock. `None` doesn't look.
+    pub monitor: Option<Duration>,
+    /// How the report is printed.
+    pub report: Format,
+    /// Where to write the timeline. `None` keeps none.
+    pub trace: Option<PathBuf>,
+    /// Waits longer than this raise the starvation alarm.
+    pub starving: Duration,
+    /// `--help` was given.
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            count: None,
+            names_file: None,
+            strategy: Strategy::default(),
+            naive_pause: None,
+            lifecycle: Lifecycle::default(),
+            virtual_time: false,
+            monitor: None,
+            report: Format::default(),
+            starving: report::DEFAULT_STARVING,
+            trace: None,
+            help: false,
+        }
+    }
+}
+
+/// Parses the command line arguments, without the program name.
+///
+/// Flags take their value either as the next argument (`--count 7`) or
+/// after an equals sign (`--count=7`).
+///
+/// # Failures
+/// Returns a message for unknown flags, missing or malformed values, and
+/// tables too small to eat at.
+pub fn parse<I>(args: I) -> Result<Options, String>
+    where I: IntoIterator<Item = String>
+{
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    let mut stop_flag: Option<String> = None;
+
+    while let Some(arg) = args.next() {
+        // Split "--flag=value" into its two halves.
+        let (flag, inline_value) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => {
+                (arg[..i].to_string(), Some(arg[i + 1..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+
+        match &flag[..] {
+            "-h" | "--help" => options.help = true,
+            "--count" => {
+                let count = number(&flag, inline_value.or_else(|| args.next()))?;
+                if count < MIN_PHILOSOPHERS {
+                    return Err(format!("--count must be at least {}", MIN_PHILOSOPHERS));
+                }
+                options.count = Some(count);
+            }
+            "--names-file" => {
+                let path = text(&flag, inline_value.or_else(|| args.next()))?;
+                options.names_file = Some(PathBuf::from(path));
+            }
+            "--strategy" => {
+                options.strategy = text(&flag, inline_value.or_else(|| args.next()))?.parse()?;
+            }
+            "--naive-pause" => {
+                let millis = number(&flag, inline_value.or_else(|| args.next()))?;
+                options.naive_pause = Some(Duration::from_millis(millis));
+            }
+            "--meals" | "--run-time" => {
+                if let Some(ref other) = stop_flag {
+                    return Err(format!("{} and {} can't be used together", other, flag));
+                }
+                let value: u64 = number(&flag, inline_value.or_else(|| args.next()))?;
+                if value == 0 {
+                    return Err(format!("{} must be at least 1", flag));
+                }
+                options.lifecycle.stop = if flag == "--meals" {
+                    Stop::Meals(value as usize)
+                } else {
+                    Stop::After(Duration::from_millis(value))
+                };
+                stop_flag = Some(flag);
+            }
+            "--think" => {
+                let think = text(&flag, inline_value.or_else(|| args.next()))?;
+                options.lifecycle.think = think.parse()?;
+            }
+            "--eat" => {
+                let eat = text(&flag, inline_value.or_else(|| args.next()))?;
+                options.lifecycle.eat = eat.parse()?;
+            }
+            "--report" => {
+                options.report = text(&flag, inline_value.or_else(|| args.next()))?.parse()?;
+                options.lifecycle.narrate = options.report == Format::Table;
+            }
+            "--starving" => {
+                let millis = number(&flag, inline_value.or_else(|| args.next()))?;
+                options.starving = Duration::from_millis(millis);
+            }
+            "--trace" => {
+                let path = text(&flag, inline_value.or_else(|| args.next()))?;
+                options.trace = Some(PathBuf::from(path));
+            }
+            "--virtual" => options.virtual_time = true,
+            "--seed" => {
+                options.lifecycle.seed = Some(number(&flag, inline_value.or_else(|| args.next()))?);
+            }
+            "--monitor" => {
+                let millis = number(&flag, inline_value.or_else(|| args.next()))?;
+                if millis == 0 {
+                    return Err("--monitor must be at least 1".to_string());
+                }
+                options.monitor = Some(Duration::from_millis(millis));
+            }
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+    if options.naive_pause.is_some() && options.strategy != Strategy::Naive {
+        return Err("--naive-pause only works with --strategy naive".to_string());
+    }
+    Ok(options)
+}
+
+/// The value of a flag that takes any text.
+fn text(flag: &str, value: Option<String>) -> Result<String, String> {
+    match value {
+        Some(ref value) if value.is_empty() => Err(format!("{} needs a value", flag)),
+        Some(value) => Ok(value),
+        None => Err(format!("{} needs a value", flag)),
+    }
+}
+
+/// Parses the value of a numeric flag.
+fn number<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = match value {
+        Some(value) => value,
+        None => return Err(format!("{} needs a value", flag)),
+    };
+    value.trim().parse()
+        .map_err(|_| format!("{} expects a whole number, got '{}'", flag, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lifecycle::Distribution;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(Ok(Options::default()), parse(args("")));
+    }
+
+    #[test]
+    fn count_and_names() {
+        let options = parse(args("--count 7 --names-file=names.txt --trace out.json")).unwrap();
+        assert_eq!(Some(7), options.count);
+        assert_eq!(Some(PathBuf::from("names.txt")), options.names_file);
+        assert_eq!(Some(PathBuf::from("out.json")), options.trace);
+    }
+
+    #[test]
+    fn strategies() {
+        assert_eq!(Strategy::Ordered, parse(args("")).unwrap().strategy);
+        assert_eq!(Strategy::ChandyMisra, parse(args("--strategy chandy-misra")).unwrap().strategy);
+        assert!(parse(args("--strategy polite")).is_err());
+
+        assert_eq!(None, parse(args("--strategy naive")).unwrap().naive_pause);
+        assert_eq!(Some(Duration::from_millis(10)),
+                   parse(args("--naive-pause=10 --strategy naive")).unwrap().naive_pause);
+        assert_eq!(Err("--naive-pause only works with --strategy naive".to_string()),
+                   parse(args("--naive-pause 10")));
+    }
+
+    #[test]
+    fn lifecycle() {
+        assert_eq!(Lifecycle::default(), parse(args("")).unwrap().lifecycle);
+        let options = parse(args("--meals 5 --think fixed:0 --eat=exp:50")).unwrap();
+        assert_eq!(Lifecycle {
+            think: Distribution::Fixed(0),
+            eat: Distribution::Exponential(50),
+            stop: Stop::Meals(5),
+            narrate: true,
+            seed: None,
+        }, options.lifecycle);
+        assert_eq!(Stop::After(Duration::from_millis(1500)),
+                   parse(args("--run-time 1500")).unwrap().lifecycle.stop);
+        assert_eq!(Err("--meals and --run-time can't be used together".to_string()),
+                   parse(args("--meals 2 --run-time 100")));
+        assert!(parse(args("--meals 0")).is_err());
+        assert!(parse(args("--eat 100")).is_err());
+    }
+
+    #[test]
+    fn virtual_time() {
+        let options = parse(args("")).unwrap();
+        assert!(!options.virtual_time);
+        assert_eq!(None, options.lifecycle.seed);
+
+        let options = parse(args("--virtual --seed=42")).unwrap();
+        assert!(options.virtual_time);
+        assert_eq!(Some(42), options.lifecycle.seed);
+        assert!(parse(args("--seed -1")).is_err());
+    }
+
+    #[test]
+    fn monitor() {
+        assert_eq!(None, parse(args("")).unwrap().monitor);
+        assert_eq!(Some(Duration::from_millis(50)), parse(args("--monitor=50")).unwrap().monitor);
+        assert_eq!(Err("--monitor must be at least 1".to_string()), parse(args("--monitor 0")));
+    }
+
+    #[test]
+    fn report() {
+        let options = parse(args("")).unwrap();
+        assert_eq!(Format::Table, options.report);
+        assert!(options.lifecycle.narrate);
+        assert_eq!(Duration::from_millis(1000), options.starving);
+
+        let options = parse(args("--report json --starving=250")).unwrap();
+        assert_eq!(Format::Json, options.report);
+        assert!(!options.lifecycle.narrate);
+        assert_eq!(Duration::from_millis(250), options.starving);
+        assert!(parse(args("--report csv")).is_err());
+    }
+
+    #[test]
+    fn help() {
+        assert!(parse(args("--help")).unwrap().help);
+        assert!(parse(args("-h")).unwrap().help);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse(args("--count")).is_err());
+        assert!(parse(args("--count five")).is_err());
+        assert_eq!(Err("--count must be at least 2".to_string()), parse(args("--count 1")));
+        assert!(parse(args("--names-file")).is_err());
+        assert!(parse(args("--forks 3")).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! What a run looked like: who ate how often and how long they waited.
+//!
+//! A philosopher's wait is the time from getting hungry to holding both
+//! forks. Meals per philosopher tell whether the forks were shared
+//! fairly, waits tell whether anybody starved meanwhile.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde_json;
+
+use strategy::Strategy;
+
+/// How long a wait may get before the report raises the alarm.
+pub const DEFAULT_STARVING: Duration = Duration::from_millis(1000);
+
+/// How the report is printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// A table for people, after everybody said what they were doing.
+    #[default]
+    Table,
+    /// One JSON object and nothing else, for scripts.
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match &s.trim().to_ascii_lowercase()[..] {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown report format '{}', pick table or json", s)),
+        }
+    }
+}
+
+/// How one philosopher fared. Times are in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diner {
+    pub name: String,
+    pub meals: usize,
+    pub total_wait_ms: f64,
+    pub max_wait_ms: f64,
+    pub p50_wait_ms: f64,
+    pub p90_wait_ms: f64,
+    pub p99_wait_ms: f64,
+    /// Waited longer than the report's threshold at least once.
+    pub starving: bool,
+}
+
+/// How the whole table fared.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    pub strategy: String,
+    pub elapsed_s: f64,
+    pub meals: usize,
+    /// Meals per second, for the table as a whole.
+    pub throughput: f64,
+    /// Jain's fairness index over the meals eaten: 1 when everybody ate
+    /// as often, down to 1 / N when one philosopher ate everything.
+    pub fairness: f64,
+    pub starving_threshold_ms: f64,
+    /// Names of everyone who waited longer than the threshold.
+    pub starving: Vec<String>,
+    pub diners: Vec<Diner>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// The `p`th percentile of `sorted` by nearest rank, zero if it is empty.
+pub fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::default();
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Jain's fairness index of `amounts`: (Σx)² / (n · Σx²). Taken as 1 when
+/// there is nothing to share.
+pub fn jain_index(amounts: &[f64]) -> f64 {
+    let sum: f64 = amounts.iter().sum();
+    let squares: f64 = amounts.iter().map(|x| x * x).sum();
+    if squares == 0.0 {
+        1.0
+    } else {
+        sum * sum / (amounts.len() as f64 * squares)
+    }
+}
+
+impl Report {
+    /// The report of a run with `strategy` that took `elapsed`, where
+    /// `waits[i]` holds every wait of philosopher `names[i]`, one per meal.
+    pub fn new<S: AsRef<str>>(strategy: Strategy,
+                              names: &[S],
+                              waits: &[Vec<Duration>],
+                              elapsed: Duration,
+                              starving: Duration)
+                              -> Report {
+        let diners: Vec<Diner> = names.iter().zip(waits).map(|(name, waits)| {
+            let mut sorted = waits.clone();
+            sorted.sort();
+            let max = sorted.last().cloned().unwrap_or_default();
+            Diner {
+                name: name.as_ref().to_string(),
+                meals: waits.len(),
+                total_wait_ms: millis(waits.iter().sum()),
+                max_wait_ms: millis(max),
+                p50_wait_ms: millis(percentile(&sorted, 50.0)),
+                p90_wait_ms: millis(percentile(&sorted, 90.0)),
+                p99_wait_ms: millis(percentile(&sorted, 99.0)),
+                starving: max > starving,
+            }
+        }).collect();
+
+        let meals = diners.iter().map(|d| d.meals).sum();
+        let meal_counts: Vec<f64> = diners.iter().map(|d| d.meals as f64).collect();
+        Report {
+            strategy: strategy.to_string(),
+            elapsed_s: elapsed.as_secs_f64(),
+            meals,
+            throughput: if elapsed > Duration::default() {
+                meals as f64 / elapsed.as_secs_f64()
+            } else {
+                0.0
+            },
+            fairness: jain_index(&meal_counts),
+            starving_threshold_ms: millis(starving),
+            starving: diners.iter().filter(|d| d.starving).map(|d| d.name.clone()).collect(),
+            diners,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a report is always valid JSON")
+    }
+}
+
+impl fmt::Display for Report {
+    /// The human readable table, ending with a newline.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.diners.iter().map(|d| d.name.chars().count()).chain(Some(11)).max().unwrap();
+        writeln!(f, "{:<width$}  {:>5}  {:>10}  {:>8}  {:>6}  {:>6}  {:>6}",
+                 "philosopher", "meals", "total wait", "max wait", "p50", "p90", "p99",
+                 width = width)?;
+        for d in &self.diners {
+            writeln!(f, "{:<width$}  {:>5}  {:>7.0} ms  {:>5.0} ms  {:>6.0}  {:>6.0}  {:>6.0}{}",
+                     d.name, d.meals, d.total_wait_ms, d.max_wait_ms,
+                     d.p50_wait_ms, d.p90_wait_ms, d.p99_wait_ms,
+                     if d.starving { "  starving" } else { "" },
+                     width = width)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "All {} philosophers ate {} meals in {:.2}s with the {} strategy.",
+                 self.diners.len(), self.meals, self.elapsed_s, self.strategy)?;
+        writeln!(f, "Throughput {:.2} meals/s, fairness {:.3}.", self.throughput, self.fairness)?;
+        if self.starving.is_empty() {
+            writeln!(f, "Nobody waited longer than {:.0} ms.", self.starving_threshold_ms)
+        } else {
+            writeln!(f, "Starvation alarm: {} waited longer than {:.0} ms.",
+                     self.starving.join(", "), self.starving_threshold_ms)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: &[u64]) -> Vec<Duration> {
+        millis.iter().map(|&m| Duration::from_millis(m)).collect()
+    }
+
+    #[test]
+    fn percentiles_by_nearest_rank() {
+        let sorted = ms(&[10, 20, 30, 40, 50, 60, 70, 80, 90, 100]);
+        assert_eq!(Duration::from_millis(50), percentile(&sorted, 50.0));
+        assert_eq!(Duration::from_millis(90), percentile(&sorted, 90.0));
+        assert_eq!(Duration::from_millis(100), percentile(&sorted, 99.0));
+        assert_eq!(Duration::from_millis(10), percentile(&sorted, 0.0));
+        assert_eq!(Duration::default(), percentile(&[], 50.0));
+    }
+
+    #[test]
+    fn jain() {
+        assert_eq!(1.0, jain_index(&[3.0, 3.0, 3.0]));
+        assert_eq!(0.25, jain_index(&[8.0, 0.0, 0.0, 0.0]));
+        assert_eq!(1.0, jain_index(&[0.0, 0.0]));
+        assert!((jain_index(&[1.0, 2.0]) - 0.9).abs() < 1e-9);
+    }
+
+    fn report() -> Report {
+        Report::new(Strategy::Waiter,
+                    &["Kant", "Hypatia"],
+                    &[ms(&[0, 100, 20]), ms(&[1500])],
+                    Duration::from_secs(2),
+                    DEFAULT_STARVING)
+    }
+
+    #[test]
+    fn per_philosopher() {
+        let report = report();
+        assert_eq!(Diner {
+            name: "Kant".to_string(),
+            meals: 3,
+            total_wait_ms: 120.0,
+            max_wait_ms: 100.0,
+            p50_wait_ms: 20.0,
+            p90_wait_ms: 100.0,
+            p99_wait_ms: 100.0,
+            starving: false,
+        }, report.diners[0]);
+        assert!(report.diners[1].starving);
+        assert_eq!(vec!["Hypatia".to_string()], report.starving);
+    }
+
+    #[test]
+    fn whole_table() {
+        let report = report();
+        assert_eq!(4, report.meals);
+        assert_eq!(2.0, report.throughput);
+        assert_eq!(0.8, report.fairness);
+    }
+
+    #[test]
+    fn table() {
+        assert_eq!("\
+philosopher  meals  total wait  max wait     p50     p90     p99
+Kant             3      120 ms    100 ms      20     100     100
+Hypatia          1     1500 ms   1500 ms    1500    1500    1500  starving
+
+All 2 philosophers ate 4 meals in 2.00s with the waiter strategy.
+Throughput 2.00 meals/s, fairness 0.800.
+Starvation alarm: Hypatia waited longer than 1000 ms.
+", report().to_string());
+    }
+
+    #[test]
+    fn json() {
+        let json: serde_json::Value = serde_json::from_str(&report().to_json()).unwrap();
+        assert_eq!("waiter", json["strategy"]);
+        assert_eq!(0.8, json["fairness"]);
+        assert_eq!("Hypatia", json["starving"][0]);
+        assert_eq!(3, json["diners"][0]["meals"]);
+        assert_eq!(1500.0, json["diners"][1]["p99_wait_ms"]);
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(Ok(Format::Json), "JSON".parse());
+        assert!("xml".parse::<Format>().is_err());
+    }
+}
@@ -0,0 +1,397 @@
+//! Ways of picking up two forks.
+//!
+//! Every philosopher needs the forks on both sides, and each of those is
+//! wanted by a neighbour too. How the two forks are taken decides whether
+//! the table can deadlock and how fairly meals are shared:
+//!
+//! - `Ordered` takes the lower numbered fork first. No cycle of waiting
+//!   philosophers can form.
+//! - `Waiter` lets at most N - 1 philosophers reach for forks at once, so
+//!   at least one of them always gets both.
+//! - `Backoff` takes one fork, tries the other, and on failure puts the
+//!   first one back and waits a random while before trying again.
+//! - `ChandyMisra` passes forks along with a clean/dirty flag: a dirty
+//!   fork goes to a neighbour who asks for it, a clean one stays until its
+//!   holder has eaten. Hungry philosophers take turns.
+//! - `Naive` takes the left fork, then the right one. If everybody holds
+//!   their left fork at once, nobody ever eats.
+//!
+//! All waiting goes through the table's `Clock`, so the strategies work
+//! the same in real and in virtual time.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use rand::{Rng, StdRng};
+
+use fork::ForkGuard;
+use lifecycle;
+use sync::Mutex;
+use table::{Philosopher, Table};
+
+/// Shortest pause `Backoff` makes after failing to get the second fork.
+const MIN_BACKOFF: Duration = Duration::from_millis(1);
+
+/// Longest pause `Backoff` makes, however often it failed.
+const MAX_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Both forks of a philosopher, held until this is dropped.
+pub struct Forks<'a> {
+    _guards: [ForkGuard<'a>; 2],
+}
+
+/// Locks forks `first` and `second` of `table` for `philosopher`, in that
+/// order.
+fn lock<'a>(philosopher: &Philosopher, table: &'a Table, first: usize, second: usize) -> Forks<'a> {
+    let first = table.fork(first).lock(philosopher.index());
+    let second = table.fork(second).lock(philosopher.index());
+    Forks { _guards: [first, second] }
+}
+
+/// A way of picking up forks, shared by everyone at the table.
+pub trait ForkStrategy: Send + Sync {
+    /// Blocks until `philosopher` holds both of their forks.
+    fn pick_up<'a>(&self, philosopher: &Philosopher, table: &'a Table) -> Forks<'a>;
+
+    /// Called once `philosopher` has dropped the forks returned by
+    /// `pick_up`, for strategies that keep track of who is eating.
+    fn put_down(&self, _philosopher: &Philosopher, _table: &Table) {}
+}
+
+/// The strategies there are, as chosen on the command line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strategy {
+    #[default]
+    Ordered,
+    Waiter,
+    Backoff,
+    ChandyMisra,
+    Naive,
+}
+
+impl Strategy {
+    /// Every strategy, the default first.
+    pub const ALL: [Strategy; 5] = [
+        Strategy::Ordered,
+        Strategy::Waiter,
+        Strategy::Backoff,
+        Strategy::ChandyMisra,
+        Strategy::Naive,
+    ];
+
+    /// The name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Ordered => "ordered",
+            Strategy::Waiter => "waiter",
+            Strategy::Backoff => "backoff",
+            Strategy::ChandyMisra => "chandy-misra",
+            Strategy::Naive => "naive",
+        }
+    }
+
+    /// The strategy for a table of `count` philosophers. `seed` is for
+    /// the ones that need random numbers, see `lifecycle::rng`.
+    pub fn build(self, count: usize, seed: Option<u64>) -> Box<dyn ForkStrategy> {
+        match self {
+            Strategy::Ordered => Box::new(Ordered),
+            Strategy::Waiter => Box::new(Waiter::new(count - 1)),
+            // Its own stream, after the ones of the philosophers.
+            Strategy::Backoff => Box::new(Backoff::new(lifecycle::rng(seed, count))),
+            Strategy::ChandyMisra => Box::new(ChandyMisra::new(count)),
+            Strategy::Naive => Box::new(Naive::default()),
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Strategy, String> {
+        Strategy::ALL.iter().cloned()
+            .find(|strategy| strategy.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Strategy::ALL.iter().map(|s| s.name()).collect();
+                format!("unknown strategy '{}', pick {}", s, names.join(", "))
+            })
+    }
+}
+
+/// The lower numbered fork first.
+pub struct Ordered;
+
+impl ForkStrategy for Ordered {
+    fn pick_up<'a>(&self, philosopher: &Philosopher, table: &'a Table) -> Forks<'a> {
+        let (first, second) = philosopher.forks_in_order();
+        lock(philosopher, table, first, second)
+    }
+}
+
+/// Left, then right. Can deadlock.
+#[derive(Default)]
+pub struct Naive {
+    pause: Option<Duration>,
+}
+
+impl Naive {
+    /// A `Naive` that looks at its left fork for `pause` before reaching
+    /// for the right one. Without a pause the window for a deadlock is a
+    /// few instructions wide and hardly ever hit; with one, a table that
+    /// starts together locks up.
+    pub fn pausing(pause: Duration) -> Naive {
+        Naive { pause: Some(pause) }
+    }
+}
+
+impl ForkStrategy for Naive {
+    fn pick_up<'a>(&self, philosopher: &Philosopher, table: &'a Table) -> Forks<'a> {
+        let me = philosopher.index();
+        let left = table.fork(philosopher.left()).lock(me);
+        if let Some(pause) = self.pause {
+            table.clock().sleep(me, pause);
+        }
+        let right = table.fork(philosopher.right()).lock(me);
+        Forks { _guards: [left, right] }
+    }
+}
+
+/// Somebody who only lets `seats` philosophers reach for forks at once.
+pub struct Waiter {
+    free: Mutex<usize>,
+}
+
+impl Waiter {
+    pub fn new(seats: usize) -> Waiter {
+        Waiter { free: Mutex::new(seats) }
+    }
+}
+
+impl ForkStrategy for Waiter {
+    fn pick_up<'a>(&self, philosopher: &Philosopher, table: &'a Table) -> Forks<'a> {
+        table.clock().block_until(philosopher.index(), &mut || {
+            let mut free = self.free.lock().unwrap();
+            if *free == 0 {
+                return false;
+            }
+            *free -= 1;
+            true
+        });
+        // With one seat short, left then right can't go round in a circle.
+        lock(philosopher, table, philosopher.left(), philosopher.right())
+    }
+
+    fn put_down(&self, _philosopher: &Philosopher, table: &Table) {
+        *self.free.lock().unwrap() += 1;
+        table.clock().notify();
+    }
+}
+
+/// Left fork, then try the right one; on failure start over after a
+/// random pause that grows with every failure.
+pub struct Backoff {
+    rng: Mutex<StdRng>,
+}
+
+impl Backoff {
+    pub fn new(rng: StdRng) -> Backoff {
+        Backoff { rng: Mutex::new(rng) }
+    }
+}
+
+impl ForkStrategy for Backoff {
+    fn pick_up<'a>(&self, philosopher: &Philosopher, table: &'a Table) -> Forks<'a> {
+        let mut limit = MIN_BACKOFF;
+
+        loop {
+            let left = table.fork(philosopher.left()).lock(philosopher.index());
+            if let Some(right) = table.fork(philosopher.right()).try_lock(philosopher.index()) {
+                return Forks { _guards: [left, right] };
+            }
+            drop(left);
+
+            // Random, so two neighbours don't retry in lockstep forever.
+            let micros = limit.as_micros() as u64;
+            let pause = self.rng.lock().unwrap().gen_range(micros / 2, micros + 1);
+            table.clock().sleep(philosopher.index(), Duration::from_micros(pause));
+            limit = (limit * 2).min(MAX_BACKOFF);
+        }
+    }
+}
+
+/// Who has which fork in `ChandyMisra`.
+struct ForkState {
+    /// The philosopher holding each fork.
+    owner: Vec<usize>,
+    /// Whether each fork was eaten with since it was handed over.
+    dirty: Vec<bool>,
+    /// Whether each philosopher is eating.
+    eating: Vec<bool>,
+}
+
+/// The Chandy–Misra solution.
+///
+/// The paper passes requests and forks around as messages. Here all of it
+/// is one shared state, and a hungry philosopher takes a fork from its
+/// owner whenever the owner would have sent it: when it is dirty and the
+/// owner isn't eating. The fork mutexes are still locked while eating, so
+/// the table looks the same from outside as with the other strategies.
+pub struct ChandyMisra {
+    state: Mutex<ForkState>,
+}
+
+impl ChandyMisra {
+    /// Every fork starts dirty with the lower numbered of the two
+    /// philosophers next to it, which makes sure nobody waits in a circle.
+    pub fn new(count: usize) -> ChandyMisra {
+        let owner = (0..count).map(|fork| fork.min((fork + count - 1) % count)).collect();
+        ChandyMisra {
+            state: Mutex::new(ForkState {
+                owner,
+                dirty: vec![true; count],
+                eating: vec![false; count],
+            }),
+        }
+    }
+}
+
+impl ForkStrategy for ChandyMisra {
+    fn pick_up<'a>(&self, philosopher: &Philosopher, table: &'a Table) -> Forks<'a> {
+        let me = philosopher.index();
+        let forks = [philosopher.left(), philosopher.right()];
+
+        table.clock().block_until(me, &mut || {
+            let mut state = self.state.lock().unwrap();
+            for &fork in &forks {
+                let owner = state.owner[fork];
+                if owner != me && state.dirty[fork] && !state.eating[owner] {
+                    state.owner[fork] = me;
+                    state.dirty[fork] = false;
+                }
+            }
+            let mine = forks.iter().all(|&fork| state.owner[fork] == me);
+            state.eating[me] = mine;
+            mine
+        });
+
+        let (first, second) = philosopher.forks_in_order();
+        lock(philosopher, table, first, second)
+    }
+
+    fn put_down(&self, philosopher: &Philosopher, table: &Table) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.eating[philosopher.index()] = false;
+            state.dirty[philosopher.left()] = true;
+            state.dirty[philosopher.right()] = true;
+        }
+        table.clock().notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::VirtualClock;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+
+    #[test]
+    fn names_round_trip() {
+        for &strategy in &Strategy::ALL {
+            assert_eq!(Ok(strategy), strategy.name().parse());
+        }
+        assert_eq!(Ok(Strategy::ChandyMisra), "Chandy-Misra".parse());
+        assert_eq!(Err("unknown strategy 'polite', pick ordered, waiter, backoff, \
+                        chandy-misra, naive".to_string()),
+                   "polite".parse::<Strategy>());
+    }
+
+    /// Lets `count` philosophers take `meals` quick meals each with
+    /// `strategy`, checking that neighbours never eat at the same time.
+    fn feast(strategy: Strategy, count: usize, meals: usize, virtual_time: bool) {
+        let names: Vec<String> = (0..count).map(|i| i.to_string()).collect();
+        let table = if virtual_time {
+            Table::with_clock(count, Arc::new(VirtualClock::new(count)))
+        } else {
+            Table::new(count)
+        };
+        let table = Arc::new(table);
+        let strategy: Arc<dyn ForkStrategy> = Arc::from(strategy.build(count, Some(1)));
+        let eating: Arc<Vec<AtomicBool>> = Arc::new((0..count).map(|_| AtomicBool::new(false)).collect());
+
+        let handles: Vec<_> = Philosopher::seat(&names).into_iter().map(|p| {
+            let (table, strategy, eating) = (table.clone(), strategy.clone(), eating.clone());
+            thread::spawn(move || {
+                let me = p.index();
+                table.clock().arrive(me);
+                for _ in 0..meals {
+                    let forks = strategy.pick_up(&p, &table);
+                    assert!(!eating[(me + count - 1) % count].load(Ordering::SeqCst));
+                    assert!(!eating[(me + 1) % count].load(Ordering::SeqCst));
+                    eating[me].store(true, Ordering::SeqCst);
+                    if virtual_time {
+                        table.clock().sleep(me, Duration::from_millis(1));
+                    } else {
+                        thread::yield_now();
+                    }
+                    eating[me].store(false, Ordering::SeqCst);
+                    drop(forks);
+                    strategy.put_down(&p, &table);
+                }
+                table.clock().leave(me);
+            })
+        }).collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn every_safe_strategy_feeds_everyone() {
+        for &strategy in &Strategy::ALL {
+            if strategy != Strategy::Naive {
+                feast(strategy, 5, 50, false);
+                feast(strategy, 2, 50, false);
+            }
+        }
+    }
+
+    #[test]
+    fn every_safe_strategy_feeds_everyone_in_virtual_time() {
+        for &strategy in &Strategy::ALL {
+            if strategy != Strategy::Naive {
+                feast(strategy, 5, 50, true);
+                feast(strategy, 2, 50, true);
+            }
+        }
+    }
+
+    #[test]
+    fn waiter_keeps_a_seat_free() {
+        let waiter = Waiter::new(1);
+        let table = Table::new(3);
+        let philosophers = Philosopher::seat(&["a", "b", "c"]);
+
+        let forks = waiter.pick_up(&philosophers[0], &table);
+        assert_eq!(0, *waiter.free.lock().unwrap());
+        drop(forks);
+        waiter.put_down(&philosophers[0], &table);
+        assert_eq!(1, *waiter.free.lock().unwrap());
+    }
+
+    #[test]
+    fn chandy_misra_starts_with_the_lower_neighbour() {
+        let state = ChandyMisra::new(4).state.into_inner().unwrap();
+        assert_eq!(vec![0, 0, 1, 2], state.owner);
+        assert!(state.dirty.iter().all(|&dirty| dirty));
+    }
+}
@@ -0,0 +1,32 @@
+//! The locks the table is built from.
+//!
+//! Normally these are the standard library's. Built with `--cfg loom`
+//! they are loom's instead, whose model checker then tries every order
+//! in which the philosophers' threads can take them (see tests/loom.rs).
+//! `Arc` stays the standard one either way: it decides nothing about who
+//! gets a fork, and `Arc<dyn Clock>` needs the real thing. So does the
+//! lock on the `WaitGraph`, which is bookkeeping for the monitor and is
+//! never held while taking another lock.
+
+#[cfg(not(loom))]
+pub use std::sync::{Condvar, Mutex, MutexGuard};
+
+#[cfg(loom)]
+pub use loom::sync::{Condvar, Mutex, MutexGuard};
+
+pub mod thread {
+    use std::time::Duration;
+
+    /// Sleeps for `duration`.
+    #[cfg(not(loom))]
+    pub fn sleep(duration: Duration) {
+        ::std::thread::sleep(duration);
+    }
+
+    /// Under loom time doesn't pass: a sleep is just a chance for the
+    /// other threads to run.
+    #[cfg(loom)]
+    pub fn sleep(_duration: Duration) {
+        ::loom::thread::yield_now();
+    }
+}
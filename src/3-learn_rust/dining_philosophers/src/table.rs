@@ -0,0 +1,371 @@
+//! The table, its forks and who sits where.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clock::{Clock, RealClock};
+use fork::{Fork, WaitGraph};
+use lifecycle::{self, Lifecycle};
+use strategy::ForkStrategy;
+use trace::Trace;
+
+/// Who sits at the table when no names are given.
+pub const DEFAULT_NAMES: [&str; 5] = [
+    "Judith Butler",
+    "Gilles Deleuze",
+    "Karl Marx",
+    "Emma Goldman",
+    "Michel Foucault",
+];
+
+/// Fewest philosophers that make a table: with one there would be a
+/// single fork, needed twice.
+pub const MIN_PHILOSOPHERS: usize = 2;
+
+pub struct Philosopher {
+    name: String,
+    left: usize, // Vector index of left fork.
+    right: usize, // Vector index of right fork.
+}
+
+impl Philosopher {
+    pub fn new(name: &str, left: usize, right: usize) -> Philosopher {
+        Philosopher {
+            name: name.to_string(),
+            left,
+            right,
+        }
+    }
+
+    /// Seats everyone in `names` around a table in that order. Philosopher
+    /// `i` has fork `i` on the left and fork `i + 1` on the right, and the
+    /// last one shares fork 0 with the first.
+    ///
+    /// # Panics
+    /// Panics with fewer than `MIN_PHILOSOPHERS` names.
+    pub fn seat<S: AsRef<str>>(names: &[S]) -> Vec<Philosopher> {
+        assert!(names.len() >= MIN_PHILOSOPHERS,
+                "a table needs at least {} philosophers", MIN_PHILOSOPHERS);
+        let count = names.len();
+        names.iter().enumerate()
+            .map(|(i, name)| Philosopher::new(name.as_ref(), i, (i + 1) % count))
+            .collect()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Where the philosopher sits, counting from 0. This is also the
+    /// number of the fork on their left.
+    pub fn index(&self) -> usize {
+        self.left
+    }
+
+    pub fn left(&self) -> usize {
+        self.left
+    }
+
+    pub fn right(&self) -> usize {
+        self.right
+    }
+
+    /// The two forks, the lower numbered one first. If everyone picked up
+    /// their left fork first, all could hold one fork and wait for the
+    /// other forever. Taking them in this order, the philosopher between
+    /// the highest and the lowest fork reaches for the other side first,
+    /// so somebody always gets to eat.
+    pub fn forks_in_order(&self) -> (usize, usize) {
+        if self.left < self.right {
+            (self.left, self.right)
+        } else {
+            (self.right, self.left)
+        }
+    }
+
+    /// Thinks, gets hungry and eats, over and over until `lifecycle`
+    /// says to stop. Returns how long each meal was waited for.
+    pub fn dine(&self, table: &Table, strategy: &dyn ForkStrategy, lifecycle: &Lifecycle)
+                -> Vec<Duration> {
+        let clock = table.clock();
+        let mut rng = lifecycle::rng(lifecycle.seed, self.index());
+        clock.arrive(self.index());
+        let seated = clock.now();
+        let mut waits = Vec::new();
+
+        while lifecycle.goes_on(waits.len(), clock.now() - seated) {
+            self.change(table, lifecycle, None, Some("thinking"));
+            let wanted = lifecycle.think.sample(&mut rng);
+            clock.sleep(self.index(), lifecycle.thinking_time(wanted, clock.now() - seated));
+            if !lifecycle.goes_on(waits.len(), clock.now() - seated) {
+                self.change(table, lifecycle, Some("thinking"), None);
+                break;
+            }
+
+            self.change(table, lifecycle, Some("thinking"), Some("hungry"));
+            waits.push(self.eat(table, strategy, lifecycle, lifecycle.eat.sample(&mut rng)));
+        }
+        clock.leave(self.index());
+        waits
+    }
+
+    /// One meal that takes `meal`, once both forks are in hand. Returns
+    /// how long getting them took. The philosopher is hungry until then.
+    pub fn eat(&self, table: &Table, strategy: &dyn ForkStrategy, lifecycle: &Lifecycle,
+               meal: Duration) -> Duration {
+        let clock = table.clock();
+        let hungry = clock.now();
+        // Nobody else can use the forks until `forks` is dropped. How
+        // they are picked up, and whether that can go wrong, is up to
+        // the strategy.
+        let forks = strategy.pick_up(self, table);
+        let waited = clock.now() - hungry;
+
+        self.change(table, lifecycle, Some("hungry"), Some("eating"));
+
+        clock.sleep(self.index(), meal);
+
+        self.change(table, lifecycle, Some("eating"), None);
+
+        drop(forks);
+        strategy.put_down(self, table);
+        waited
+    }
+
+    /// Tells the trace and, if asked to, everybody else, that the
+    /// philosopher is done with state `from` and starts `to`.
+    fn change(&self, table: &Table, lifecycle: &Lifecycle, from: Option<&str>, to: Option<&str>) {
+        if let Some(trace) = table.trace() {
+            if let Some(from) = from {
+                trace.end(self.index(), from);
+            }
+            if let Some(to) = to {
+                trace.begin(self.index(), to);
+            }
+        }
+        if lifecycle.narrate {
+            match (from, to) {
+                (_, Some(to)) => println!("{} is {}.", self.name, to),
+                (Some("eating"), None) => println!("{} is done eating.", self.name),
+                _ => {}
+            }
+        }
+    }
+}
+
+pub struct Table {
+    // A vector of forks. Each fork is a Mutex underneath: only one
+    // thread can hold it at once, which is the exact requirement with
+    // our forks. On top of that it tells `graph` who holds it and who
+    // waits for it, so a deadlock can be spotted from outside.
+    forks: Vec<Fork>,
+    graph: Arc<WaitGraph>,
+    clock: Arc<dyn Clock>,
+    trace: Option<Arc<Trace>>,
+}
+
+impl Table {
+    /// A table with `forks` forks, one per philosopher, in real time.
+    pub fn new(forks: usize) -> Table {
+        Table::with_clock(forks, Arc::new(RealClock::new()))
+    }
+
+    /// A table with `forks` forks where time is kept by `clock`.
+    pub fn with_clock(forks: usize, clock: Arc<dyn Clock>) -> Table {
+        let graph = Arc::new(WaitGraph::new(forks, forks));
+        Table {
+            forks: (0..forks).map(|i| Fork::new(i, graph.clone(), clock.clone())).collect(),
+            graph,
+            clock,
+            trace: None,
+        }
+    }
+
+    /// The same table, with everything that happens at it recorded in
+    /// `trace`.
+    pub fn with_trace(self, trace: Arc<Trace>) -> Table {
+        Table {
+            forks: self.forks.into_iter().map(|fork| fork.with_trace(trace.clone())).collect(),
+            trace: Some(trace),
+            ..self
+        }
+    }
+
+    pub fn forks(&self) -> usize {
+        self.forks.len()
+    }
+
+    /// Fork number `i`.
+    pub fn fork(&self, i: usize) -> &Fork {
+        &self.forks[i]
+    }
+
+    /// Who holds and waits for which fork, for a deadlock `monitor`.
+    pub fn graph(&self) -> &Arc<WaitGraph> {
+        &self.graph
+    }
+
+    /// Where the time at the table comes from.
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Where the timeline is recorded, if anywhere.
+    pub fn trace(&self) -> Option<&Arc<Trace>> {
+        self.trace.as_ref()
+    }
+}
+
+/// Why a names file could not be used.
+#[derive(Debug)]
+pub enum NamesError {
+    /// The file could not be opened or read.
+    Io(io::Error),
+    /// The file names fewer than `MIN_PHILOSOPHERS` philosophers.
+    TooFew(usize),
+}
+
+impl fmt::Display for NamesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NamesError::Io(ref err) => write!(f, "could not read the names file: {}", err),
+            NamesError::TooFew(count) => {
+                write!(f, "the names file has {} name(s), a table needs at least {}",
+                       count, MIN_PHILOSOPHERS)
+            }
+        }
+    }
+}
+
+impl Error for NamesError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            NamesError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NamesError {
+    fn from(err: io::Error) -> NamesError {
+        NamesError::Io(err)
+    }
+}
+
+/// Reads one name per line. Blank lines and lines starting with `#` are
+/// skipped.
+pub fn read_names<R: BufRead>(reader: R) -> Result<Vec<String>, NamesError> {
+    let mut names = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let name = line.trim();
+        if !name.is_empty() && !name.starts_with('#') {
+            names.push(name.to_string());
+        }
+    }
+    if names.len() < MIN_PHILOSOPHERS {
+        return Err(NamesError::TooFew(names.len()));
+    }
+    Ok(names)
+}
+
+/// Reads the names file at `path`, see `read_names`.
+pub fn names_from_file<P: AsRef<Path>>(path: P) -> Result<Vec<String>, NamesError> {
+    read_names(BufReader::new(File::open(path)?))
+}
+
+/// The first `count` of `names`. If there aren't enough, the rest are
+/// called "Philosopher 6", "Philosopher 7" and so on.
+pub fn pick_names<S: AsRef<str>>(names: &[S], count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| match names.get(i) {
+            Some(name) => name.as_ref().to_string(),
+            None => format!("Philosopher {}", i + 1),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::VirtualClock;
+    use lifecycle::{Distribution, Stop};
+    use strategy::Strategy;
+
+    #[test]
+    fn everyone_shares_forks_with_their_neighbours() {
+        let philosophers = Philosopher::seat(&["a", "b", "c", "d"]);
+        let seats: Vec<(usize, usize)> = philosophers.iter().map(|p| (p.left(), p.right())).collect();
+        assert_eq!(vec![(0, 1), (1, 2), (2, 3), (3, 0)], seats);
+        assert_eq!("d", philosophers[3].name());
+    }
+
+    #[test]
+    fn a_virtual_dinner_to_the_millisecond() {
+        let clock = Arc::new(VirtualClock::new(2));
+        let table = Arc::new(Table::with_clock(2, clock.clone()));
+        let strategy: Arc<dyn ForkStrategy> = Arc::from(Strategy::Ordered.build(2, None));
+        let lifecycle = Lifecycle {
+            think: Distribution::Fixed(10),
+            eat: Distribution::Fixed(20),
+            stop: Stop::Meals(2),
+            narrate: false,
+            seed: None,
+        };
+
+        let handles: Vec<_> = Philosopher::seat(&["a", "b"]).into_iter().map(|p| {
+            let (table, strategy) = (table.clone(), strategy.clone());
+            ::std::thread::spawn(move || p.dine(&table, &*strategy, &lifecycle))
+        }).collect();
+        let waits: Vec<Vec<Duration>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        // Both get hungry at 10 ms and "a" is first to ask. From then on
+        // each one's thinking is shorter than the other's meal, so they
+        // take turns: a eats 10-30, b 30-50, a 50-70, b 70-90.
+        let ms = Duration::from_millis;
+        assert_eq!(vec![vec![ms(0), ms(10)], vec![ms(20), ms(10)]], waits);
+        assert_eq!(ms(90), clock.now());
+    }
+
+    #[test]
+    fn the_last_philosopher_reaches_the_other_way() {
+        let philosophers = Philosopher::seat(&DEFAULT_NAMES);
+        let order: Vec<(usize, usize)> = philosophers.iter().map(|p| p.forks_in_order()).collect();
+        // The hand-written table of the tutorial: Michel Foucault picks
+        // up fork 0 before fork 4.
+        assert_eq!(vec![(0, 1), (1, 2), (2, 3), (3, 4), (0, 4)], order);
+
+        let pair = Philosopher::seat(&["a", "b"]);
+        assert_eq!((0, 1), pair[0].forks_in_order());
+        assert_eq!((0, 1), pair[1].forks_in_order());
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 2")]
+    fn one_is_not_a_table() {
+        Philosopher::seat(&["alone"]);
+    }
+
+    #[test]
+    fn names() {
+        let names = read_names("# thinkers\nHannah Arendt\n\n  Simone Weil  \n".as_bytes()).unwrap();
+        assert_eq!(vec!["Hannah Arendt", "Simone Weil"], names);
+        match read_names("Hannah Arendt\n".as_bytes()) {
+            Err(NamesError::TooFew(1)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert_eq!(vec!["Hannah Arendt"], pick_names(&names, 1));
+        assert_eq!(vec!["Hannah Arendt", "Simone Weil", "Philosopher 3"], pick_names(&names, 3));
+    }
+
+    #[test]
+    fn a_fork_per_seat() {
+        assert_eq!(7, Table::new(7).forks());
+    }
+}
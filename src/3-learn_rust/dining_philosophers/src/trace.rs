@@ -0,0 +1,227 @@
+//! A timeline of the whole dinner in the Chrome Trace Event Format.
+//!
+//! Every philosopher and every fork gets a track of its own. Philosophers
+//! go from thinking to hungry to eating, with a mark for each fork they
+//! pick up or release; a fork shows who holds it. The file opens in
+//! chrome://tracing and in Perfetto (https://ui.perfetto.dev), which is a
+//! lot easier to follow than lines printed by a dozen threads at once.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde_json;
+
+use clock::Clock;
+
+/// The trace's "process" holding the philosophers' tracks.
+const PHILOSOPHERS: u32 = 1;
+/// The trace's "process" holding the forks' tracks.
+const FORKS: u32 = 2;
+
+/// Where an event is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Track {
+    Philosopher(usize),
+    Fork(usize),
+}
+
+impl Track {
+    fn ids(self) -> (u32, usize) {
+        match self {
+            Track::Philosopher(i) => (PHILOSOPHERS, i),
+            Track::Fork(i) => (FORKS, i),
+        }
+    }
+}
+
+/// One event, as the format has it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Event {
+    pub name: String,
+    /// "B" and "E" begin and end a slice, "i" is a mark and "M" names
+    /// a track.
+    pub ph: &'static str,
+    /// Microseconds on the table's clock.
+    pub ts: f64,
+    pub pid: u32,
+    pub tid: usize,
+    /// How far a mark reaches: "t" is its own track only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<serde_json::Value>,
+}
+
+/// The layout of a trace file.
+#[derive(Serialize)]
+#[allow(non_snake_case)]
+struct TraceFile<'a> {
+    traceEvents: &'a [Event],
+    displayTimeUnit: &'static str,
+}
+
+/// Collects events from every thread at the table.
+pub struct Trace {
+    clock: Arc<dyn Clock>,
+    names: Vec<String>,
+    events: Mutex<Vec<Event>>,
+}
+
+impl Trace {
+    /// A trace of `names` sitting around `forks` forks, with every track
+    /// named. Events are stamped with the time on `clock`.
+    pub fn new<S: AsRef<str>>(names: &[S], forks: usize, clock: Arc<dyn Clock>) -> Trace {
+        let names: Vec<String> = names.iter().map(|n| n.as_ref().to_string()).collect();
+        let metadata = |name: &str, pid: u32, tid: usize, value: String| Event {
+            name: name.to_string(),
+            ph: "M",
+            ts: 0.0,
+            pid,
+            tid,
+            s: None,
+            args: Some(json!({ "name": value })),
+        };
+
+        let mut events = vec![
+            metadata("process_name", PHILOSOPHERS, 0, "Philosophers".to_string()),
+            metadata("process_name", FORKS, 0, "Forks".to_string()),
+        ];
+        for (i, name) in names.iter().enumerate() {
+            events.push(metadata("thread_name", PHILOSOPHERS, i, name.clone()));
+        }
+        for fork in 0..forks {
+            events.push(metadata("thread_name", FORKS, fork, format!("fork {}", fork)));
+        }
+        Trace { clock, names, events: Mutex::new(events) }
+    }
+
+    fn record(&self, track: Track, ph: &'static str, name: String) {
+        let (pid, tid) = track.ids();
+        let event = Event {
+            name,
+            ph,
+            ts: self.clock.now().as_secs_f64() * 1_000_000.0,
+            pid,
+            tid,
+            s: if ph == "i" { Some("t") } else { None },
+            args: None,
+        };
+        self.events.lock().unwrap().push(event);
+    }
+
+    /// `philosopher` starts `state`: thinking, hungry or eating.
+    pub fn begin(&self, philosopher: usize, state: &str) {
+        self.record(Track::Philosopher(philosopher), "B", state.to_string());
+    }
+
+    /// `philosopher` is done with `state`.
+    pub fn end(&self, philosopher: usize, state: &str) {
+        self.record(Track::Philosopher(philosopher), "E", state.to_string());
+    }
+
+    /// `philosopher` now holds `fork`.
+    pub fn picked_up(&self, philosopher: usize, fork: usize) {
+        self.record(Track::Philosopher(philosopher), "i", format!("picked up fork {}", fork));
+        self.record(Track::Fork(fork), "B", self.names[philosopher].clone());
+    }
+
+    /// `philosopher` put `fork` back on the table.
+    pub fn released(&self, philosopher: usize, fork: usize) {
+        self.record(Track::Fork(fork), "E", self.names[philosopher].clone());
+        self.record(Track::Philosopher(philosopher), "i", format!("released fork {}", fork));
+    }
+
+    /// Everything recorded so far, in order.
+    pub fn events(&self) -> Vec<Event> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// The trace as a JSON object.
+    pub fn to_json(&self) -> String {
+        let events = self.events.lock().unwrap();
+        serde_json::to_string(&TraceFile { traceEvents: &events, displayTimeUnit: "ms" })
+            .expect("a trace is always valid JSON")
+    }
+
+    /// Writes the trace to `path`, replacing whatever is there.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let events = self.events.lock().unwrap();
+        let trace = TraceFile { traceEvents: &events, displayTimeUnit: "ms" };
+        serde_json::to_writer(&mut file, &trace)?;
+        // Dropping the writer would flush it too, but lose the error.
+        file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::RealClock;
+
+    fn trace() -> Trace {
+        Trace::new(&["Kant", "Hypatia"], 2, Arc::new(RealClock::new()))
+    }
+
+    #[test]
+    fn tracks_are_named() {
+        let trace = trace();
+        let named: Vec<(u32, usize, String)> = trace.events().into_iter()
+            .filter(|e| e.name == "thread_name")
+            .map(|e| (e.pid, e.tid, e.args.unwrap()["name"].as_str().unwrap().to_string()))
+            .collect();
+        assert_eq!(vec![(PHILOSOPHERS, 0, "Kant".to_string()),
+                        (PHILOSOPHERS, 1, "Hypatia".to_string()),
+                        (FORKS, 0, "fork 0".to_string()),
+                        (FORKS, 1, "fork 1".to_string())],
+                   named);
+    }
+
+    #[test]
+    fn forks_show_who_holds_them() {
+        let trace = trace();
+        trace.begin(1, "hungry");
+        trace.picked_up(1, 0);
+        trace.end(1, "hungry");
+        trace.released(1, 0);
+
+        let events: Vec<(&str, String, u32, usize)> = trace.events().into_iter()
+            .filter(|e| e.ph != "M")
+            .map(|e| (e.ph, e.name, e.pid, e.tid))
+            .collect();
+        assert_eq!(vec![("B", "hungry".to_string(), PHILOSOPHERS, 1),
+                        ("i", "picked up fork 0".to_string(), PHILOSOPHERS, 1),
+                        ("B", "Hypatia".to_string(), FORKS, 0),
+                        ("E", "hungry".to_string(), PHILOSOPHERS, 1),
+                        ("E", "Hypatia".to_string(), FORKS, 0),
+                        ("i", "released fork 0".to_string(), PHILOSOPHERS, 1)],
+                   events);
+
+        let times: Vec<f64> = trace.events().iter().map(|e| e.ts).collect();
+        assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn json_layout() {
+        let trace = trace();
+        trace.picked_up(0, 1);
+        let json: serde_json::Value = serde_json::from_str(&trace.to_json()).unwrap();
+        assert_eq!("ms", json["displayTimeUnit"]);
+        let events = json["traceEvents"].as_array().unwrap();
+        let mark = &events[events.len() - 2];
+        assert_eq!("i", mark["ph"]);
+        assert_eq!("t", mark["s"]);
+        assert!(mark.get("args").is_none());
+    }
+
+    #[test]
+    fn failed_writes_are_reported() {
+        // The trace fits in the writer's buffer, so only the last flush
+        // finds the device full.
+        if Path::new("/dev/full").exists() {
+            assert!(trace().save("/dev/full").is_err());
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// Model checked tests: loom runs the philosophers' threads in every order
+// that makes a difference to the locks they take, instead of whichever
+// order the operating system happens to pick. A strategy that passes can
+// not deadlock at that table, however unlucky the timing.
+//
+// Only the fork locks and the strategies' own locks are modelled, since
+// those are what decide who waits for whom. The `Arc`s sharing the table
+// and the mutex guarding the wait graph stay the standard library's:
+// the graph's lock is never held while taking another one, and letting
+// loom interleave it as well makes even three philosophers take minutes.
+//
+// The table's locks only come from loom when built with --cfg loom, so
+// these tests are skipped otherwise. Run them with
+//
+//     RUSTFLAGS="--cfg loom" cargo test --release --test loom
+
+#![cfg(loom)]
+
+extern crate dining_philosophers;
+extern crate loom;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dining_philosophers::{ForkStrategy, Lifecycle, Philosopher, Strategy, Table};
+
+/// Every possible dinner of `count` philosophers who each eat one meal
+/// with `strategy`.
+fn every_dinner(strategy: Strategy, count: usize) {
+    let mut model = loom::model::Builder::new();
+    model.max_branches = 100_000;
+    model.check(move || {
+        let names: Vec<String> = (0..count).map(|i| i.to_string()).collect();
+        let table = Arc::new(Table::new(count));
+        let strategy: Arc<dyn ForkStrategy> = Arc::from(strategy.build(count, Some(0)));
+        let lifecycle = Lifecycle { narrate: false, ..Lifecycle::default() };
+
+        let handles: Vec<_> = Philosopher::seat(&names).into_iter().map(|p| {
+            let (table, strategy) = (table.clone(), strategy.clone());
+            loom::thread::spawn(move || {
+                p.eat(&table, &*strategy, &lifecycle, Duration::default());
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    });
+}
+
+/// Like `every_dinner`, but expects loom to find a dinner where everyone
+/// waits forever, and returns its description of it.
+fn find_deadlock(strategy: Strategy, count: usize) -> String {
+    let found = panic::catch_unwind(AssertUnwindSafe(|| every_dinner(strategy, count)));
+    let message = match found {
+        Ok(()) => panic!("every {} dinner of {} ended", strategy, count),
+        Err(payload) => payload.downcast_ref::<String>().cloned()
+            .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_default(),
+    };
+    assert!(message.starts_with("deadlock"), "{}", message);
+    message
+}
+
+#[test]
+fn ordered_never_deadlocks_with_three() {
+    every_dinner(Strategy::Ordered, 3);
+}
+
+#[test]
+fn ordered_never_deadlocks_with_four() {
+    every_dinner(Strategy::Ordered, 4);
+}
+
+#[test]
+fn naive_deadlocks_with_three() {
+    // Every philosopher stuck, each holding their left fork, and the
+    // main thread with them, waiting to join the first.
+    let message = find_deadlock(Strategy::Naive, 3);
+    assert_eq!(4, message.matches("Blocked").count(), "{}", message);
+    assert!(!message.contains("Runnable"), "{}", message);
+}
+
+#[test]
+fn naive_deadlocks_with_four() {
+    find_deadlock(Strategy::Naive, 4);
+}
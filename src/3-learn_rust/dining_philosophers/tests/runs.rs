@@ -0,0 +1,129 @@
+// Integration Tests: run the real binary with short meals and check what
+// it prints and how it ends.
+
+extern crate serde_json;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Runs the binary with `args` and returns its stdout and exit status.
+fn run(args: &[&str]) -> (String, Option<i32>) {
+    let output = Command::new(env!("CARGO_BIN_EXE_dining_philosophers"))
+        .args(args)
+        .output()
+        .unwrap();
+    (String::from_utf8(output.stdout).unwrap(), output.status.code())
+}
+
+#[test]
+fn naive_philosophers_deadlock_and_get_caught() {
+    // Nobody thinks, so everyone reaches for their left fork at once, and
+    // in virtual time all of them have it before anybody looks right.
+    let (stdout, status) = run(&["--virtual", "--seed", "7", "--strategy", "naive",
+                                 "--naive-pause", "10", "--think", "fixed:0", "--monitor", "20"]);
+    assert_eq!(Some(3), status, "{}", stdout);
+    assert!(stdout.ends_with(
+        "Deadlock: Judith Butler → fork 1 → Gilles Deleuze → fork 2 → Karl Marx → fork 3 → \
+         Emma Goldman → fork 4 → Michel Foucault → fork 0 → Judith Butler\n"),
+        "{}", stdout);
+    assert!(!stdout.contains("is eating"), "{}", stdout);
+}
+
+#[test]
+fn philosophers_think_and_eat_for_the_run_time() {
+    let (stdout, status) = run(&["--count", "4", "--run-time", "300", "--think", "exp:20",
+                                 "--eat", "uniform:5-15", "--strategy", "chandy-misra"]);
+    assert_eq!(Some(0), status, "{}", stdout);
+    // Everyone gets hungry several times in 300ms of short meals.
+    for name in &["Judith Butler", "Gilles Deleuze", "Karl Marx", "Emma Goldman"] {
+        let meals = stdout.matches(&format!("{} is done eating.", name)[..]).count();
+        assert!(meals >= 3, "{} ate {} times:\n{}", name, meals, stdout);
+        assert!(stdout.contains(&format!("{} is thinking.", name)[..]));
+        assert!(stdout.contains(&format!("{} is hungry.", name)[..]));
+    }
+}
+
+#[test]
+fn json_reports_are_all_there_is() {
+    let (stdout, status) = run(&["--count", "3", "--meals", "4", "--think", "fixed:0",
+                                 "--eat", "fixed:5", "--strategy", "backoff", "--report", "json"]);
+    assert_eq!(Some(0), status, "{}", stdout);
+    let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!("backoff", report["strategy"]);
+    assert_eq!(12, report["meals"]);
+    assert_eq!(1.0, report["fairness"]);
+    let diners = report["diners"].as_array().unwrap();
+    assert_eq!(3, diners.len());
+    assert_eq!("Karl Marx", diners[2]["name"]);
+    assert!(diners.iter().all(|d| d["max_wait_ms"].as_f64().unwrap() >= 0.0));
+}
+
+#[test]
+fn traces_have_a_track_per_philosopher_and_fork() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("trace.json");
+    let (stdout, status) = run(&["--count", "6", "--meals", "3", "--think", "uniform:0-5",
+                                 "--eat", "fixed:2", "--trace", path.to_str().unwrap()]);
+    assert_eq!(Some(0), status, "{}", stdout);
+
+    let trace: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    let events = trace["traceEvents"].as_array().unwrap();
+    let tracks = events.iter().filter(|e| e["name"] == "thread_name").count();
+    assert_eq!(12, tracks);
+
+    // Every slice that begins also ends, on the track it began on.
+    let mut open: HashMap<(u64, u64), Vec<String>> = HashMap::new();
+    for event in events {
+        let track = (event["pid"].as_u64().unwrap(), event["tid"].as_u64().unwrap());
+        let name = event["name"].as_str().unwrap().to_string();
+        match event["ph"].as_str().unwrap() {
+            "B" => open.entry(track).or_default().push(name),
+            "E" => assert_eq!(Some(name), open.get_mut(&track).and_then(|names| names.pop())),
+            _ => {}
+        }
+    }
+    assert!(open.values().all(|names| names.is_empty()), "{:?}", open);
+
+    let meals = events.iter().filter(|e| e["ph"] == "B" && e["name"] == "eating").count();
+    let pickups = events.iter()
+        .filter(|e| e["name"].as_str().unwrap().starts_with("picked up fork"))
+        .count();
+    assert_eq!(18, meals);
+    assert_eq!(36, pickups);
+}
+
+#[test]
+fn virtual_runs_are_instant_and_repeatable() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    for strategy in &["ordered", "waiter", "backoff", "chandy-misra"] {
+        let runs: Vec<(String, String)> = (0..2).map(|i| {
+            let path = dir.join(format!("virtual-{}-{}.json", strategy, i));
+            let started = Instant::now();
+            // 100 meals of up to 300 ms each would take minutes for real.
+            let (stdout, status) = run(&["--virtual", "--seed", "2015", "--count", "7",
+                                         "--meals", "100", "--strategy", strategy,
+                                         "--trace", path.to_str().unwrap()]);
+            assert_eq!(Some(0), status, "{}", stdout);
+            assert!(started.elapsed() < Duration::from_secs(10));
+            assert!(stdout.contains("All 7 philosophers ate 700 meals"), "{}", stdout);
+            (stdout, fs::read_to_string(&path).unwrap())
+        }).collect();
+        assert!(runs[0] == runs[1], "{} differs between runs", strategy);
+    }
+
+    // Another seed, another evening.
+    let (first, _) = run(&["--virtual", "--seed", "1", "--report", "json"]);
+    let (second, _) = run(&["--virtual", "--seed", "2", "--report", "json"]);
+    assert!(first != second);
+}
+
+#[test]
+fn ordered_philosophers_finish_under_the_monitor() {
+    let (stdout, status) = run(&["--count", "3", "--meals", "2", "--eat", "fixed:10",
+                                 "--monitor", "5"]);
+    assert_eq!(Some(0), status, "{}", stdout);
+    assert!(!stdout.contains("Deadlock"), "{}", stdout);
+    assert!(stdout.contains("All 3 philosophers ate 6 meals"), "{}", stdout);
+}
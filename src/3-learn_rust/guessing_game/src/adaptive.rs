@@ -0,0 +1,259 @@
+//! Picking the next game from how a player did in their last ones.
+//!
+//! Two things are tuned, each by a simple rule on the recent games:
+//!
+//! - The range moves one step along `RANGES` when the player wins clearly
+//!   more or clearly less often than `TARGET_WIN_RATE`, and stays put
+//!   near the target.
+//! - The attempt budget is what a perfect binary search needs for the
+//!   new range, plus the number of extra guesses that would have been
+//!   enough in `TARGET_WIN_RATE` of the recent games.
+//!
+//! Only the leaderboard history goes in, no clock and no randomness, so
+//! the same history always gives the same plan.
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use bench::binary_search_bound;
+use game::Rules;
+use scores::Score;
+
+/// How many of a player's latest games are looked at.
+pub const RECENT_GAMES: usize = 10;
+
+/// The share of games a player should win.
+pub const TARGET_WIN_RATE: f64 = 0.7;
+
+/// How far the win rate may stray from the target before the range changes.
+pub const TOLERANCE: f64 = 0.15;
+
+/// Fewest recent games needed before the range changes.
+pub const MIN_GAMES: usize = 3;
+
+/// The sizes of range a player moves through, smallest first. Every range
+/// starts at 1.
+pub const RANGES: [u32; 7] = [10, 50, 100, 500, 1000, 5000, 10_000];
+
+/// Where a player without any history starts.
+const START_LEVEL: usize = 2;
+
+/// Most guesses allowed on top of what binary search needs.
+const MAX_EXTRA: u32 = 10;
+
+/// Guesses allowed on top of binary search when there's nothing to go by.
+const START_EXTRA: u32 = 3;
+
+/// The next game, and how it was chosen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub rules: Rules,
+    /// One sentence per decision, for the player to read.
+    pub why: Vec<String>,
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.why.join("\n"))
+    }
+}
+
+/// The rules for a range of `size` numbers from 1, with `extra` guesses
+/// on top of a perfect binary search.
+fn rules(size: u32, extra: u32) -> Rules {
+    let range = Rules { min: 1, max: size, max_attempts: None };
+    Rules { max_attempts: Some(binary_search_bound(&range) + extra), ..range }
+}
+
+/// The place in `RANGES` of the first range at least as big as the one
+/// `score` was played with.
+fn level(score: &Score) -> usize {
+    let size = score.max - score.min + 1;
+    RANGES.iter().position(|&r| r >= size).unwrap_or(RANGES.len() - 1)
+}
+
+/// How many guesses beyond a perfect binary search the game of `score`
+/// took. A lost game needed at least one more than it was allowed.
+fn extra_needed(score: &Score) -> u32 {
+    let range = Rules { min: score.min, max: score.max, max_attempts: None };
+    let needed = if score.won {
+        score.attempts
+    } else {
+        score.max_attempts.unwrap_or(score.attempts).max(score.attempts) + 1
+    };
+    needed.saturating_sub(binary_search_bound(&range))
+}
+
+/// Plans the next game of a player from their `history`, which should
+/// only hold games of that player and may be in any order.
+pub fn plan(history: &[Score]) -> Plan {
+    let mut recent: Vec<&Score> = history.iter().collect();
+    // Newest first. Ties keep the order of the file, where later is newer.
+    recent.reverse();
+    recent.sort_by_key(|s| Reverse(s.finished_at));
+    recent.truncate(RECENT_GAMES);
+
+    let latest = match recent.first() {
+        Some(latest) => latest,
+        None => {
+            let rules = rules(RANGES[START_LEVEL], START_EXTRA);
+            return Plan {
+                rules,
+                why: vec![format!("No games played yet, so you start at 1 to {} with {} attempts.",
+                                  rules.max, rules.max_attempts.unwrap())],
+            };
+        }
+    };
+    let mut why = Vec::new();
+
+    let games = recent.len();
+    let wins = recent.iter().filter(|s| s.won).count();
+    let win_rate = wins as f64 / games as f64;
+    why.push(format!("You won {} of your last {} game(s), {:.0}%. The aim is {:.0}%.",
+                     wins, games, win_rate * 100.0, TARGET_WIN_RATE * 100.0));
+
+    let from = level(latest);
+    let to = if games < MIN_GAMES {
+        why.push(format!("That is too few games to go by, so the range stays at 1 to {}.",
+                         RANGES[from]));
+        from
+    } else if win_rate > TARGET_WIN_RATE + TOLERANCE && from + 1 < RANGES.len() {
+        why.push(format!("That is well above the aim, so the range grows from 1 to {} to 1 to {}.",
+                         RANGES[from], RANGES[from + 1]));
+        from + 1
+    } else if win_rate < TARGET_WIN_RATE - TOLERANCE && from > 0 {
+        why.push(format!("That is well below the aim, so the range shrinks from 1 to {} to 1 to {}.",
+                         RANGES[from], RANGES[from - 1]));
+        from - 1
+    } else {
+        why.push(format!("The range stays at 1 to {}.", RANGES[from]));
+        from
+    };
+
+    // The smallest number of extra guesses that covers the target share
+    // of the recent games.
+    let mut extras: Vec<u32> = recent.iter().map(|s| extra_needed(s)).collect();
+    extras.sort();
+    let covered = (TARGET_WIN_RATE * games as f64).ceil() as usize;
+    let extra = extras[covered.clamp(1, games) - 1].min(MAX_EXTRA);
+
+    let rules = rules(RANGES[to], extra);
+    let perfect = rules.max_attempts.unwrap() - extra;
+    why.push(format!("A perfect binary search needs {} guesses for 1 to {}. {} extra guess(es) \
+                      were enough in {} of those games, so you get {} attempts.",
+                     perfect, rules.max, extra, covered.min(games), rules.max_attempts.unwrap()));
+
+    Plan { rules, why }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A game from 1 to `max` that ended at `finished_at`.
+    fn game(max: u32, attempts: u32, won: bool, finished_at: u64) -> Score {
+        Score {
+            player: "ada".to_string(),
+            min: 1,
+            max,
+            max_attempts: Some(10),
+            difficulty: None,
+            attempts,
+            won,
+            points: None,
+            elapsed_secs: 1.0,
+            finished_at,
+        }
+    }
+
+    #[test]
+    fn first_game() {
+        let plan = plan(&[]);
+        assert_eq!(Rules { min: 1, max: 100, max_attempts: Some(10) }, plan.rules);
+        assert_eq!("No games played yet, so you start at 1 to 100 with 10 attempts.",
+                   plan.to_string());
+    }
+
+    #[test]
+    fn winning_grows_the_range() {
+        // Binary search needs 7 guesses for 1 to 100.
+        let history: Vec<Score> = (0..5).map(|i| game(100, 7 + i % 3, true, i as u64)).collect();
+        let plan = plan(&history);
+        // 1 to 500 needs 9, and one more covered 4 of the 5 games.
+        assert_eq!(Rules { min: 1, max: 500, max_attempts: Some(10) }, plan.rules);
+        assert_eq!(plan.why, [
+            "You won 5 of your last 5 game(s), 100%. The aim is 70%.",
+            "That is well above the aim, so the range grows from 1 to 100 to 1 to 500.",
+            "A perfect binary search needs 9 guesses for 1 to 500. 1 extra guess(es) \
+             were enough in 4 of those games, so you get 10 attempts.",
+        ]);
+    }
+
+    #[test]
+    fn losing_shrinks_the_range() {
+        let history = vec![
+            game(100, 10, false, 1),
+            game(100, 10, false, 2),
+            game(100, 8, true, 3),
+        ];
+        let plan = plan(&history);
+        assert_eq!(50, plan.rules.max);
+        // Each loss needed at least 11 guesses, 4 more than binary search.
+        assert_eq!(Some(6 + 4), plan.rules.max_attempts);
+        assert!(plan.why[1].contains("shrinks from 1 to 100 to 1 to 50"), "{:?}", plan.why);
+    }
+
+    #[test]
+    fn near_the_aim_stays() {
+        let mut history: Vec<Score> = (0..7).map(|i| game(500, 9, true, i)).collect();
+        history.extend((7..10).map(|i| game(500, 10, false, i)));
+        let plan = plan(&history);
+        assert_eq!(Rules { min: 1, max: 500, max_attempts: Some(9) }, plan.rules);
+        assert_eq!("The range stays at 1 to 500.", plan.why[1]);
+    }
+
+    #[test]
+    fn only_recent_games_count() {
+        // Ten old losses, then ten wins: only the wins are looked at, even
+        // if the history isn't in order.
+        let mut history: Vec<Score> = (10..20).map(|i| game(100, 7, true, i)).collect();
+        history.extend((0..10).map(|i| game(100, 10, false, i)));
+        assert_eq!(500, plan(&history).rules.max);
+    }
+
+    #[test]
+    fn too_few_games_and_the_ends_of_the_ladder() {
+        let plan_one = plan(&[game(50, 3, true, 1)]);
+        assert_eq!(50, plan_one.rules.max);
+        // Binary search needs 6 for 1 to 50, winning in 3 takes no extra.
+        assert_eq!(Some(6), plan_one.rules.max_attempts);
+
+        let top: Vec<Score> = (0..3).map(|i| game(10_000, 14, true, i)).collect();
+        assert_eq!(10_000, plan(&top).rules.max);
+
+        let bottom: Vec<Score> = (0..3).map(|i| game(10, 10, false, i)).collect();
+        let plan_bottom = plan(&bottom);
+        assert_eq!(10, plan_bottom.rules.max);
+        assert_eq!(Some(4 + 7), plan_bottom.rules.max_attempts);
+    }
+
+    #[test]
+    fn same_history_same_plan() {
+        let history: Vec<Score> = (0..8).map(|i| game(1000, 9 + i % 4, i % 3 != 0, i as u64)).collect();
+        assert_eq!(plan(&history), plan(&history));
+    }
+
+    #[test]
+    fn every_plan_is_winnable() {
+        for &size in &RANGES {
+            for attempts in 1..20 {
+                let history: Vec<Score> = (0..5)
+                    .map(|i| game(size, attempts, attempts < 12, i))
+                    .collect();
+                let rules = plan(&history).rules;
+                assert_eq!(Ok(()), rules.validate());
+                assert!(rules.max_attempts.unwrap() >= binary_search_bound(&rules));
+            }
+        }
+    }
+}
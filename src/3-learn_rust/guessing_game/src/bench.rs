@@ -0,0 +1,182 @@
+//! Pitting the solver strategies against each other.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use game::{Game, Rules, Status};
+use i18n::Catalog;
+use secret::{SecretError, SecretSource};
+use solver::{self, Strategy};
+
+/// Most rows a histogram is printed with. Wider spreads share rows.
+const HISTOGRAM_ROWS: u32 = 20;
+/// Width of the longest histogram bar.
+const BAR_WIDTH: u32 = 40;
+
+/// How many guesses a strategy needed over a series of games.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    /// Name of the strategy.
+    pub strategy: String,
+    /// How many games were played.
+    pub games: u32,
+    /// How often each number of guesses occurred.
+    pub counts: BTreeMap<u32, u32>,
+}
+
+impl Summary {
+    /// Fewest guesses needed in any game.
+    pub fn min(&self) -> u32 {
+        self.counts.keys().next().cloned().unwrap_or(0)
+    }
+
+    /// Most guesses needed in any game.
+    pub fn max(&self) -> u32 {
+        self.counts.keys().next_back().cloned().unwrap_or(0)
+    }
+
+    /// Average number of guesses.
+    pub fn mean(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        let total: u64 = self.counts.iter().map(|(&guesses, &n)| guesses as u64 * n as u64).sum();
+        total as f64 / self.games as f64
+    }
+
+    /// A histogram, one row per number of guesses (or per range of them,
+    /// if they spread too far).
+    pub fn histogram(&self) -> String {
+        let mut histogram = String::new();
+        if self.counts.is_empty() {
+            return histogram;
+        }
+
+        let (min, max) = (self.min(), self.max());
+        let bin = (max - min) / HISTOGRAM_ROWS + 1;
+        let mut rows = BTreeMap::new();
+        for (&guesses, &n) in &self.counts {
+            *rows.entry((guesses - min) / bin).or_insert(0) += n;
+        }
+        let tallest = *rows.values().max().unwrap();
+
+        for row in 0..(max - min) / bin + 1 {
+            let n = rows.get(&row).cloned().unwrap_or(0);
+            let low = min + row * bin;
+            let label = if bin == 1 {
+                format!("{}", low)
+            } else {
+                format!("{}-{}", low, low + bin - 1)
+            };
+            // Round up, so that rare counts still get a mark.
+            let bar = (n as u64 * BAR_WIDTH as u64).div_ceil(tallest as u64) as usize;
+            histogram.push_str(&format!("{:>9} | {:<width$} {}\n", label, "#".repeat(bar), n,
+                                        width = BAR_WIDTH as usize));
+        }
+        histogram
+    }
+}
+
+impl fmt::Display for Summary {
+    /// The statistics line followed by the histogram.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::english().summary(self))
+    }
+}
+
+/// Most guesses binary search can need: ⌈log2(n + 1)⌉ for n numbers.
+///
+/// With k guesses binary search can tell apart 2^k - 1 numbers, so a
+/// range of exactly 2^k numbers already needs one guess more than
+/// ⌈log2(n)⌉.
+pub fn binary_search_bound(rules: &Rules) -> u32 {
+    let numbers = (rules.max - rules.min) as u64 + 1;
+    64 - numbers.leading_zeros()
+}
+
+/// Plays `games` games with `strategy`, taking secrets from `source`.
+///
+/// The attempt limit of `rules` is ignored, every game is played until
+/// the secret is found.
+///
+/// # Failures
+/// Passes on the first error of the source.
+pub fn run<S, Src>(strategy: &mut S, source: &mut Src, rules: &Rules, games: u32)
+    -> Result<Summary, SecretError>
+    where S: Strategy + ?Sized, Src: SecretSource + ?Sized
+{
+    let rules = Rules { max_attempts: None, ..*rules };
+    let mut counts = BTreeMap::new();
+
+    for _ in 0..games {
+        let mut game = Game::with_rules(source.next_secret(&rules)?, rules);
+        let status = solver::play(strategy, &mut game);
+        debug_assert_eq!(Status::Won, status);
+        *counts.entry(game.attempts()).or_insert(0) += 1;
+    }
+
+    Ok(Summary {
+        strategy: strategy.name().to_string(),
+        games,
+        counts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secret::{ListSource, SeededSource};
+    use solver::{BinarySearch, LinearScan};
+
+    fn rules(min: u32, max: u32) -> Rules {
+        Rules { min, max, max_attempts: None }
+    }
+
+    #[test]
+    fn bound() {
+        assert_eq!(1, binary_search_bound(&rules(5, 5)));
+        assert_eq!(7, binary_search_bound(&rules(1, 100)));
+        assert_eq!(7, binary_search_bound(&rules(1, 127)));
+        assert_eq!(8, binary_search_bound(&rules(1, 128)));
+        assert_eq!(32, binary_search_bound(&rules(0, u32::MAX - 1)));
+    }
+
+    #[test]
+    fn binary_search_stays_within_bound() {
+        for &(min, max) in &[(1, 100), (0, 127), (1, 1000), (7, 7)] {
+            let rules = rules(min, max);
+            let summary = run(&mut BinarySearch::new(), &mut SeededSource::new(1),
+                              &rules, 2000).unwrap();
+            assert!(summary.max() <= binary_search_bound(&rules), "{}", summary);
+        }
+    }
+
+    #[test]
+    fn statistics() {
+        let mut secrets = ListSource::new(vec![1, 2, 4]).unwrap();
+        let summary = run(&mut LinearScan::new(), &mut secrets, &rules(1, 4), 3).unwrap();
+        assert_eq!(1, summary.min());
+        assert_eq!(4, summary.max());
+        assert!((summary.mean() - 7.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn histogram() {
+        let mut secrets = ListSource::new(vec![1, 2, 2, 4]).unwrap();
+        let summary = run(&mut LinearScan::new(), &mut secrets, &rules(1, 4), 4).unwrap();
+        let text = summary.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!("linear: min 1, mean 2.25, max 4", lines[0]);
+        assert_eq!(5, lines.len());
+        assert!(lines[2].starts_with("        2 | ########################################"));
+        assert_eq!(format!("{:>9} | {:<40} 0", 3, ""), lines[3]);
+    }
+
+    #[test]
+    fn wide_histograms_share_rows() {
+        let summary = run(&mut LinearScan::new(), &mut SeededSource::new(1),
+                          &rules(1, 1000), 500).unwrap();
+        let rows = summary.to_string().lines().count() - 1;
+        assert!(rows <= HISTOGRAM_ROWS as usize + 1, "{} rows", rows);
+    }
+}
@@ -0,0 +1,145 @@
+//! The bundled client for `serve`.
+//!
+//! It joins under the player's name, turns what the player types into
+//! protocol lines and what the server says back into sentences. See
+//! `server` for the protocol itself.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::thread;
+
+use i18n::Catalog;
+
+/// What to send for a line the player typed, if anything.
+///
+/// "leaderboard" and "quit" are commands, protocol lines are passed on as
+/// they are, and everything else is taken for a guess. The server
+/// explains what is wrong with a guess that isn't a number.
+pub fn request_for(line: &str) -> Option<String> {
+    let line = line.trim();
+    let keyword = line.split_whitespace().next().unwrap_or("").to_uppercase();
+
+    match &keyword[..] {
+        "" => None,
+        "LEADERBOARD" | "SCORES" => Some("LEADERBOARD".to_string()),
+        "QUIT" | "EXIT" => Some("QUIT".to_string()),
+        "JOIN" | "GUESS" => Some(line.to_string()),
+        _ => Some(format!("GUESS {}", line)),
+    }
+}
+
+/// Turns a line from the server into something to show the player, in
+/// the language of `catalog`.
+pub fn describe(catalog: &Catalog, line: &str) -> String {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    // The keyword and everything after it, as the server wrote it.
+    let (keyword, rest) = line.trim().split_once(char::is_whitespace).unwrap_or((line.trim(), ""));
+
+    match (keyword, &words[..]) {
+        ("WELCOME", &[_, name, round, min, max]) => {
+            catalog.format("client_welcome",
+                           &[("name", &name), ("round", &round), ("min", &min), ("max", &max)])
+        }
+        ("SMALL", _) => catalog.get("too_small").to_string(),
+        ("BIG", _) => catalog.get("too_big").to_string(),
+        ("WIN", _) => catalog.get("client_win").to_string(),
+        ("LOST", _) => catalog.get("client_lost").to_string(),
+        ("RANGE", &[_, min, max]) => catalog.format("out_of_range", &[("min", &min), ("max", &max)]),
+        ("WINNER", &[_, name, secret]) => {
+            catalog.format("client_winner", &[("name", &name), ("secret", &secret)])
+        }
+        ("NOWINNER", &[_, secret]) => catalog.format("client_no_winner", &[("secret", &secret)]),
+        ("ROUND", &[_, round, min, max]) => {
+            catalog.format("client_round", &[("round", &round), ("min", &min), ("max", &max)])
+        }
+        ("LEADERBOARD", &[_]) => catalog.get("client_no_wins").to_string(),
+        ("LEADERBOARD", wins) => {
+            let wins: Vec<String> = wins[1..].iter().map(|w| w.replace('=', " ")).collect();
+            catalog.format("client_leaderboard", &[("wins", &wins.join(", "))])
+        }
+        ("BYE", _) => catalog.get("client_bye").to_string(),
+        ("ERROR", _) => catalog.format("client_error", &[("message", &rest.trim())]),
+        _ => line.to_string(),
+    }
+}
+
+/// Plays on `stream` as `name`: lines typed on `input` go to the server,
+/// and a second thread writes what the server says to `output`, in the
+/// language of `catalog`.
+///
+/// Returns once the server has said goodbye, after `QUIT` or the end of
+/// `input`.
+pub fn run<R, W>(stream: TcpStream, name: &str, catalog: Catalog, mut input: R, mut output: W)
+    -> io::Result<()>
+    where R: BufRead, W: Write + Send + 'static
+{
+    let from_server = BufReader::new(stream.try_clone()?);
+    let printer = thread::spawn(move || -> io::Result<()> {
+        for line in from_server.lines() {
+            writeln!(output, "{}", describe(&catalog, &line?))?;
+        }
+        Ok(())
+    });
+
+    let mut to_server = stream;
+    writeln!(to_server, "JOIN {}", name)?;
+
+    let mut quit = false;
+    let mut line = String::new();
+    while !quit && input.read_line(&mut line)? > 0 {
+        if let Some(request) = request_for(&line) {
+            // The server is gone, the printer thread will notice too.
+            if writeln!(to_server, "{}", request).is_err() {
+                break;
+            }
+            quit = request == "QUIT";
+        }
+        line.clear();
+    }
+    if !quit {
+        let _ = writeln!(to_server, "QUIT");
+    }
+
+    printer.join()
+        .unwrap_or_else(|_| Err(io::Error::other("the thread printing the server's lines panicked")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests() {
+        assert_eq!(Some("GUESS 42".to_string()), request_for("42\n"));
+        assert_eq!(Some("GUESS abc".to_string()), request_for("abc"));
+        assert_eq!(Some("LEADERBOARD".to_string()), request_for("Leaderboard"));
+        assert_eq!(Some("QUIT".to_string()), request_for("quit"));
+        assert_eq!(Some("JOIN bob".to_string()), request_for("JOIN bob"));
+        assert_eq!(None, request_for("  \n"));
+    }
+
+    #[test]
+    fn descriptions() {
+        let describe = |line| describe(Catalog::english(), line);
+        assert_eq!("Welcome, ada! Round 1: guess the number between 1 and 100.",
+                   describe("WELCOME ada 1 1 100"));
+        assert_eq!("Too small!", describe("SMALL"));
+        assert_eq!("bob won the round, the secret number was 7.", describe("WINNER bob 7"));
+        assert_eq!("Nobody won the round, the secret number was 7.", describe("NOWINNER 7"));
+        assert_eq!("Leaderboard: ada 2, bob 1", describe("LEADERBOARD ada=2 bob=1"));
+        assert_eq!("Leaderboard: nobody has won yet.", describe("LEADERBOARD"));
+        assert_eq!("Server says: JOIN first", describe("ERROR JOIN first"));
+        assert_eq!("Server says: JOIN  first", describe("  ERROR\tJOIN  first "));
+        assert_eq!("Server says: ", describe("ERROR"));
+        assert_eq!("HELLO", describe("HELLO"));
+    }
+
+    #[test]
+    fn descriptions_in_other_languages() {
+        let spanish = Catalog::new("es").unwrap();
+        assert_eq!("¡Demasiado grande!", describe(&spanish, "BIG"));
+        assert_eq!("Ronda 2: adivina el número entre 1 y 10.", describe(&spanish, "ROUND 2 1 10"));
+        // What the server itself says stays as it is.
+        assert_eq!("El servidor dice: JOIN first", describe(&spanish, "ERROR JOIN first"));
+    }
+}
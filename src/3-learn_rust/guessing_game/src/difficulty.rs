@@ -0,0 +1,117 @@
+//! Difficulty presets.
+
+use std::fmt;
+use std::str::FromStr;
+
+use game::Rules;
+use hints::Hint;
+
+/// Points a game without a preset is worth before penalties. Such a game
+/// allows every hint.
+pub const CUSTOM_BASE_POINTS: u32 = 100;
+
+/// A ready-made combination of range, attempt budget and allowed hints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+impl Difficulty {
+    /// Every preset, easiest first.
+    pub const ALL: [Difficulty; 4] =
+        [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Nightmare];
+
+    /// The name used on the command line and the leaderboard.
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Nightmare => "nightmare",
+        }
+    }
+
+    /// Range and attempt budget. Nightmare leaves exactly the guesses a
+    /// perfect binary search needs.
+    pub fn rules(self) -> Rules {
+        let (max, max_attempts) = match self {
+            Difficulty::Easy => (50, 10),
+            Difficulty::Normal => (100, 10),
+            Difficulty::Hard => (1000, 12),
+            Difficulty::Nightmare => (10_000, 14),
+        };
+        Rules { min: 1, max, max_attempts: Some(max_attempts) }
+    }
+
+    /// The hints a player may ask for.
+    pub fn allowed_hints(self) -> &'static [Hint] {
+        match self {
+            Difficulty::Easy => &Hint::ALL,
+            Difficulty::Normal => &[Hint::Warmer, Hint::Parity, Hint::Near],
+            Difficulty::Hard => &[Hint::Warmer],
+            Difficulty::Nightmare => &[],
+        }
+    }
+
+    /// Points a game at this difficulty is worth before penalties.
+    pub fn base_points(self) -> u32 {
+        match self {
+            Difficulty::Easy => 100,
+            Difficulty::Normal => 200,
+            Difficulty::Hard => 400,
+            Difficulty::Nightmare => 800,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        Difficulty::ALL.iter().cloned()
+            .find(|d| d.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown difficulty '{}', pick easy, normal, hard or nightmare", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bench::binary_search_bound;
+
+    #[test]
+    fn names_round_trip() {
+        for &difficulty in &Difficulty::ALL {
+            assert_eq!(Ok(difficulty), difficulty.name().parse());
+        }
+        assert_eq!(Ok(Difficulty::Hard), "HARD".parse());
+        assert!("impossible".parse::<Difficulty>().is_err());
+    }
+
+    #[test]
+    fn presets_are_playable() {
+        for &difficulty in &Difficulty::ALL {
+            let rules = difficulty.rules();
+            assert_eq!(Ok(()), rules.validate());
+            // A perfect player can always win.
+            assert!(rules.max_attempts.unwrap() >= binary_search_bound(&rules));
+        }
+    }
+
+    #[test]
+    fn harder_means_fewer_hints_and_more_points() {
+        for pair in Difficulty::ALL.windows(2) {
+            assert!(pair[0].allowed_hints().len() >= pair[1].allowed_hints().len());
+            assert!(pair[0].base_points() < pair[1].base_points());
+        }
+    }
+}
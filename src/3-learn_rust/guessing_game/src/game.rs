@@ -0,0 +1,287 @@
+//! The `Game` state machine.
+
+use std::cmp::Ordering;
+
+/// What the game answers to a single guess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Feedback {
+    /// The guess is smaller than the secret.
+    TooSmall,
+    /// The guess is bigger than the secret.
+    TooBig,
+    /// The guess is the secret. The game is won.
+    Correct,
+    /// The guess lies outside the range of the game.
+    /// It is not counted as an attempt.
+    OutOfRange,
+}
+
+impl From<Ordering> for Feedback {
+    /// Turns the result of `guess.cmp(&secret)` into feedback.
+    fn from(ordering: Ordering) -> Feedback {
+        match ordering {
+            Ordering::Less    => Feedback::TooSmall,
+            Ordering::Greater => Feedback::TooBig,
+            Ordering::Equal   => Feedback::Correct,
+        }
+    }
+}
+
+/// Where a game stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Status {
+    /// Still waiting for guesses.
+    Playing,
+    /// The secret was found.
+    Won,
+    /// The attempt limit ran out before the secret was found.
+    Lost,
+}
+
+/// The range a secret is drawn from and how many guesses are allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    /// Smallest possible secret.
+    pub min: u32,
+    /// Largest possible secret.
+    pub max: u32,
+    /// How many guesses the player gets. `None` means unlimited.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for Rules {
+    /// The classic game: a number from 1 to 100, as many guesses as you like.
+    fn default() -> Rules {
+        Rules {
+            min: 1,
+            max: 100,
+            max_attempts: None,
+        }
+    }
+}
+
+impl Rules {
+    /// Whether `n` lies inside the range, bounds included.
+    pub fn contains(&self, n: u32) -> bool {
+        self.min <= n && n <= self.max
+    }
+
+    /// Checks that the rules describe a playable game.
+    ///
+    /// # Failures
+    /// Returns a message if the range is empty, if `max` is `u32::MAX`
+    /// (secrets are drawn from the half-open range `min..max + 1`), or if
+    /// the attempt limit is zero.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min > self.max {
+            return Err(format!("--min ({}) must not be bigger than --max ({})",
+                               self.min, self.max));
+        }
+        if self.max == u32::MAX {
+            return Err(format!("--max must be smaller than {}", u32::MAX));
+        }
+        if self.max_attempts == Some(0) {
+            return Err("--max-attempts must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A single round of the guessing game.
+///
+/// A `Game` starts out knowing its secret and counts every guess made
+/// until the secret is found or the attempts run out.
+#[derive(Debug)]
+pub struct Game {
+    secret: u32,
+    rules: Rules,
+    history: Vec<u32>,
+    status: Status,
+}
+
+impl Game {
+    /// Starts a new game with the given secret number and the default rules.
+    pub fn new(secret: u32) -> Game {
+        Game::with_rules(secret, Rules::default())
+    }
+
+    /// Starts a new game with the given secret number and rules.
+    ///
+    /// # Panics
+    /// Panics if the rules are invalid or the secret is outside their range.
+    pub fn with_rules(secret: u32, rules: Rules) -> Game {
+        if let Err(message) = rules.validate() {
+            panic!("invalid rules: {}", message);
+        }
+        assert!(rules.contains(secret),
+                "secret {} is outside {}..={}", secret, rules.min, rules.max);
+
+        Game {
+            secret,
+            rules,
+            history: Vec::new(),
+            status: Status::Playing,
+        }
+    }
+
+    /// Compares `guess` with the secret and returns the feedback.
+    ///
+    /// Guesses outside the range, and guesses made once the game is
+    /// finished, are still answered but not counted as attempts.
+    pub fn guess(&mut self, guess: u32) -> Feedback {
+        if !self.rules.contains(guess) {
+            return Feedback::OutOfRange;
+        }
+
+        let feedback = Feedback::from(guess.cmp(&self.secret));
+
+        if self.status == Status::Playing {
+            self.history.push(guess);
+            if feedback == Feedback::Correct {
+                self.status = Status::Won;
+            } else if self.attempts_left() == Some(0) {
+                self.status = Status::Lost;
+            }
+        }
+
+        feedback
+    }
+
+    /// The number of guesses made so far.
+    pub fn attempts(&self) -> u32 {
+        self.history.len() as u32
+    }
+
+    /// The guesses counted so far, oldest first.
+    pub fn history(&self) -> &[u32] {
+        &self.history
+    }
+
+    /// How many guesses are left, or `None` if there is no limit.
+    pub fn attempts_left(&self) -> Option<u32> {
+        self.rules.max_attempts.map(|max| max - self.attempts())
+    }
+
+    /// The rules this game is played with.
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// The secret number.
+    ///
+    /// Front-ends should only reveal it once the game is finished.
+    pub fn secret(&self) -> u32 {
+        self.secret
+    }
+
+    /// Where the game stands.
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Whether the game is over, won or lost.
+    pub fn is_finished(&self) -> bool {
+        self.status != Status::Playing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(min: u32, max: u32, max_attempts: Option<u32>) -> Rules {
+        Rules { min, max, max_attempts }
+    }
+
+    #[test]
+    fn new_game_is_not_finished() {
+        let game = Game::new(50);
+        assert_eq!(0, game.attempts());
+        assert_eq!(Status::Playing, game.status());
+        assert!(!game.is_finished());
+    }
+
+    #[test]
+    fn guess_gives_ordering_feedback() {
+        let mut game = Game::new(50);
+        assert_eq!(Feedback::TooSmall, game.guess(1));
+        assert_eq!(Feedback::TooBig, game.guess(100));
+        assert_eq!(Feedback::Correct, game.guess(50));
+    }
+
+    #[test]
+    fn correct_guess_finishes_game() {
+        let mut game = Game::new(7);
+        game.guess(3);
+        game.guess(7);
+        assert!(game.is_finished());
+        assert_eq!(Status::Won, game.status());
+        assert_eq!(2, game.attempts());
+    }
+
+    #[test]
+    fn guesses_after_win_are_not_counted() {
+        let mut game = Game::new(7);
+        game.guess(7);
+        assert_eq!(Feedback::TooBig, game.guess(8));
+        assert_eq!(1, game.attempts());
+        assert!(game.is_finished());
+    }
+
+    #[test]
+    fn history_holds_counted_guesses() {
+        let mut game = Game::with_rules(15, rules(10, 20, None));
+        game.guess(12);
+        game.guess(30);
+        game.guess(15);
+        game.guess(16);
+        assert_eq!(&[12, 15], game.history());
+    }
+
+    #[test]
+    fn out_of_range_guess_is_not_counted() {
+        let mut game = Game::with_rules(15, rules(10, 20, Some(1)));
+        assert_eq!(Feedback::OutOfRange, game.guess(9));
+        assert_eq!(Feedback::OutOfRange, game.guess(21));
+        assert_eq!(0, game.attempts());
+        assert_eq!(Status::Playing, game.status());
+    }
+
+    #[test]
+    fn running_out_of_attempts_loses() {
+        let mut game = Game::with_rules(15, rules(10, 20, Some(2)));
+        game.guess(10);
+        assert_eq!(Some(1), game.attempts_left());
+        game.guess(20);
+        assert_eq!(Some(0), game.attempts_left());
+        assert_eq!(Status::Lost, game.status());
+        assert!(game.is_finished());
+    }
+
+    #[test]
+    fn winning_on_last_attempt_wins() {
+        let mut game = Game::with_rules(15, rules(10, 20, Some(1)));
+        assert_eq!(Feedback::Correct, game.guess(15));
+        assert_eq!(Status::Won, game.status());
+    }
+
+    #[test]
+    fn unlimited_attempts() {
+        let game = Game::new(1);
+        assert_eq!(None, game.attempts_left());
+    }
+
+    #[test]
+    fn validate_rejects_bad_rules() {
+        assert!(rules(5, 4, None).validate().is_err());
+        assert!(rules(1, u32::MAX, None).validate().is_err());
+        assert!(rules(1, 10, Some(0)).validate().is_err());
+        assert!(rules(4, 4, Some(1)).validate().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "outside")]
+    fn secret_must_be_in_range() {
+        Game::with_rules(30, rules(10, 20, None));
+    }
+}
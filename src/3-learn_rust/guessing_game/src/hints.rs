@@ -0,0 +1,291 @@
+//! Hints the player can buy, and what they cost.
+//!
+//! Every game starts with a number of points (see `difficulty`). Every
+//! guess after the first and every hint takes some of them away, and a
+//! lost game scores nothing. `Scorecard` shows the sum at the end.
+
+use std::fmt;
+
+use game::{Game, Status};
+use i18n::Catalog;
+
+/// Points taken for every guess after the first.
+pub const GUESS_PENALTY: u32 = 10;
+
+/// Divisors the divisibility hint asks about, one more each time.
+const DIVISORS: [u32; 3] = [3, 5, 7];
+
+/// How close "near" means.
+pub const NEAR_DISTANCE: u32 = 10;
+
+/// The kinds of hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    /// Is the last guess closer to the secret than the one before?
+    Warmer,
+    /// Is the secret even or odd?
+    Parity,
+    /// Is the secret divisible by 3? By 5? By 7?
+    Divisibility,
+    /// Is the last guess within `NEAR_DISTANCE` of the secret?
+    Near,
+}
+
+impl Hint {
+    /// Every hint, cheapest first.
+    pub const ALL: [Hint; 4] = [Hint::Warmer, Hint::Parity, Hint::Divisibility, Hint::Near];
+
+    /// The word the player types after "hint".
+    pub fn name(self) -> &'static str {
+        match self {
+            Hint::Warmer => "warmer",
+            Hint::Parity => "parity",
+            Hint::Divisibility => "divisible",
+            Hint::Near => "near",
+        }
+    }
+
+    /// Points the hint takes away.
+    pub fn cost(self) -> u32 {
+        match self {
+            Hint::Warmer => 5,
+            Hint::Parity => 10,
+            Hint::Divisibility => 15,
+            Hint::Near => 20,
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Hint> {
+        Hint::ALL.iter().cloned().find(|h| h.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/// What a hint reveals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clue {
+    /// Compared with the guess before, the last guess is closer (`Warmer`),
+    /// further away (`Colder`) or just as far (`Same`).
+    Warmer,
+    Colder,
+    Same,
+    Even,
+    Odd,
+    Divisible { by: u32, yes: bool },
+    Near { guess: u32, yes: bool },
+}
+
+impl fmt::Display for Clue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::english().clue(self))
+    }
+}
+
+/// Why a hint was refused. Refused hints cost nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintError {
+    /// The difficulty doesn't allow this hint.
+    NotAllowed(Hint),
+    /// The hint needs more guesses to talk about.
+    TooEarly(Hint),
+    /// All divisors have been asked about already.
+    UsedUp(Hint),
+}
+
+impl fmt::Display for HintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::english().hint_error(self))
+    }
+}
+
+/// The hints allowed in a game, and the ones bought so far.
+#[derive(Debug, Clone)]
+pub struct Hints {
+    allowed: Vec<Hint>,
+    used: Vec<Hint>,
+}
+
+impl Hints {
+    pub fn new(allowed: &[Hint]) -> Hints {
+        Hints { allowed: allowed.to_vec(), used: Vec::new() }
+    }
+
+    pub fn allowed(&self) -> &[Hint] {
+        &self.allowed
+    }
+
+    /// Hints bought so far, in order.
+    pub fn used(&self) -> &[Hint] {
+        &self.used
+    }
+
+    /// Points spent on hints.
+    pub fn cost(&self) -> u32 {
+        self.used.iter().map(|h| h.cost()).sum()
+    }
+
+    /// Buys a `hint` about `game`.
+    pub fn ask(&mut self, hint: Hint, game: &Game) -> Result<Clue, HintError> {
+        if !self.allowed.contains(&hint) {
+            return Err(HintError::NotAllowed(hint));
+        }
+
+        let secret = game.secret();
+        let history = game.history();
+        let distance = |guess: u32| guess.abs_diff(secret);
+
+        let clue = match hint {
+            Hint::Warmer => {
+                if history.len() < 2 {
+                    return Err(HintError::TooEarly(hint));
+                }
+                let last = distance(history[history.len() - 1]);
+                let before = distance(history[history.len() - 2]);
+                if last < before {
+                    Clue::Warmer
+                } else if last > before {
+                    Clue::Colder
+                } else {
+                    Clue::Same
+                }
+            }
+            Hint::Parity if secret.is_multiple_of(2) => Clue::Even,
+            Hint::Parity => Clue::Odd,
+            Hint::Divisibility => {
+                let asked = self.used.iter().filter(|&&h| h == Hint::Divisibility).count();
+                match DIVISORS.get(asked) {
+                    Some(&by) => Clue::Divisible { by, yes: secret.is_multiple_of(by) },
+                    None => return Err(HintError::UsedUp(hint)),
+                }
+            }
+            Hint::Near => match history.last() {
+                Some(&guess) => Clue::Near { guess, yes: distance(guess) <= NEAR_DISTANCE },
+                None => return Err(HintError::TooEarly(hint)),
+            },
+        };
+
+        self.used.push(hint);
+        Ok(clue)
+    }
+
+    /// The list shown for a plain "hint", in English. See `Catalog::menu`.
+    pub fn menu(&self) -> String {
+        Catalog::english().menu(self)
+    }
+}
+
+/// How a game's points add up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scorecard {
+    /// Points the game started with.
+    pub base: u32,
+    /// Guesses after the first, `GUESS_PENALTY` each.
+    pub extra_guesses: u32,
+    /// Points spent on hints.
+    pub hint_cost: u32,
+    pub won: bool,
+}
+
+impl Scorecard {
+    pub fn new(base: u32, game: &Game, hints: &Hints) -> Scorecard {
+        Scorecard {
+            base,
+            extra_guesses: game.attempts().saturating_sub(1),
+            hint_cost: hints.cost(),
+            won: game.status() == Status::Won,
+        }
+    }
+
+    /// The final score. Never below zero, and zero for a lost game.
+    pub fn total(&self) -> u32 {
+        if !self.won {
+            return 0;
+        }
+        self.base
+            .saturating_sub(self.extra_guesses * GUESS_PENALTY)
+            .saturating_sub(self.hint_cost)
+    }
+}
+
+impl fmt::Display for Scorecard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::english().scorecard(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::Rules;
+
+    fn game(secret: u32, guesses: &[u32]) -> Game {
+        let mut game = Game::with_rules(secret, Rules { min: 1, max: 100, max_attempts: Some(5) });
+        for &guess in guesses {
+            game.guess(guess);
+        }
+        game
+    }
+
+    #[test]
+    fn warmer_compares_last_two_guesses() {
+        let mut hints = Hints::new(&Hint::ALL);
+        assert_eq!(Err(HintError::TooEarly(Hint::Warmer)), hints.ask(Hint::Warmer, &game(50, &[10])));
+        assert_eq!(Ok(Clue::Warmer), hints.ask(Hint::Warmer, &game(50, &[10, 60])));
+        assert_eq!(Ok(Clue::Colder), hints.ask(Hint::Warmer, &game(50, &[45, 60])));
+        assert_eq!(Ok(Clue::Same), hints.ask(Hint::Warmer, &game(50, &[40, 60])));
+        // Only the granted hints cost anything.
+        assert_eq!(3 * Hint::Warmer.cost(), hints.cost());
+    }
+
+    #[test]
+    fn parity_and_divisibility() {
+        let mut hints = Hints::new(&Hint::ALL);
+        let game = game(35, &[]);
+        assert_eq!(Ok(Clue::Odd), hints.ask(Hint::Parity, &game));
+        assert_eq!(Ok(Clue::Divisible { by: 3, yes: false }), hints.ask(Hint::Divisibility, &game));
+        assert_eq!(Ok(Clue::Divisible { by: 5, yes: true }), hints.ask(Hint::Divisibility, &game));
+        assert_eq!(Ok(Clue::Divisible { by: 7, yes: true }), hints.ask(Hint::Divisibility, &game));
+        assert_eq!(Err(HintError::UsedUp(Hint::Divisibility)),
+                   hints.ask(Hint::Divisibility, &game));
+    }
+
+    #[test]
+    fn near() {
+        let mut hints = Hints::new(&Hint::ALL);
+        assert_eq!(Err(HintError::TooEarly(Hint::Near)), hints.ask(Hint::Near, &game(50, &[])));
+        assert_eq!(Ok(Clue::Near { guess: 40, yes: true }), hints.ask(Hint::Near, &game(50, &[40])));
+        assert_eq!(Ok(Clue::Near { guess: 61, yes: false }), hints.ask(Hint::Near, &game(50, &[61])));
+    }
+
+    #[test]
+    fn disallowed_hints_are_refused() {
+        let mut hints = Hints::new(&[Hint::Warmer]);
+        assert_eq!(Err(HintError::NotAllowed(Hint::Parity)), hints.ask(Hint::Parity, &game(5, &[])));
+        assert_eq!(0, hints.cost());
+        assert_eq!("No hints at this difficulty.", Hints::new(&[]).menu());
+        assert_eq!("Hints: hint warmer (-5)", hints.menu());
+    }
+
+    #[test]
+    fn scoring() {
+        let mut hints = Hints::new(&Hint::ALL);
+        let won = game(50, &[10, 60, 50]);
+        hints.ask(Hint::Parity, &won).unwrap();
+        let card = Scorecard::new(100, &won, &hints);
+        assert_eq!(100 - 2 * GUESS_PENALTY - Hint::Parity.cost(), card.total());
+        assert_eq!("Score: 100 - 20 for 2 extra guess(es) - 10 for hints = 70 points.",
+                   card.to_string());
+
+        let lost = game(50, &[1, 2, 3, 4, 5]);
+        assert_eq!(0, Scorecard::new(100, &lost, &hints).total());
+
+        // Points never go negative.
+        let slow = game(5, &[1, 2, 3, 4, 5]);
+        assert_eq!(0, Scorecard::new(30, &slow, &hints).total());
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Some(Hint::Divisibility), Hint::parse("Divisible"));
+        assert_eq!(None, Hint::parse("cheat"));
+    }
+}
@@ -0,0 +1,604 @@
+//! The number game as a small HTTP/JSON API, for playing from a web page
+//! on the same machine.
+//!
+//! ```text
+//! POST /games                  start a game, answers 201 with its id and range
+//! POST /games/{id}/guesses     {"guess": 50}, answers with the feedback
+//! GET  /games/{id}             where the game stands
+//! ```
+//!
+//! Every answer is a JSON object; failures look like `{"error": "..."}`
+//! with a 4xx status. Games live in memory only and are forgotten once
+//! nobody has touched them for a while.
+//!
+//! As with `server`, `Sessions` holds the rules and knows nothing about
+//! sockets: it takes a `Request` and the current time and gives back a
+//! `Response`. `respond` reads one request off any reader and writes the
+//! answer to any writer, which is all `serve` does with each connection
+//! and all the tests need to play without a network. The HTTP spoken is
+//! the bare minimum: one request per connection, bodies sized by
+//! `Content-Length`.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json;
+
+use game::{Feedback, Game, Rules, Status};
+use secret::SecretSource;
+
+/// Port `http` listens on when none is given.
+pub const DEFAULT_HTTP_PORT: u16 = 8080;
+
+/// Seconds a game is kept without any request for it, when `--expire`
+/// isn't given.
+pub const DEFAULT_EXPIRE_SECS: u64 = 600;
+
+/// Longest request body accepted. A guess needs a few bytes.
+const MAX_BODY: usize = 4096;
+
+/// Most header lines accepted in one request.
+const MAX_HEADERS: usize = 64;
+
+/// Longest request or header line accepted, in bytes.
+const MAX_LINE: usize = 8192;
+
+/// How long a connection may keep `serve` waiting for the next bytes of
+/// its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Why a request could not be read.
+#[derive(Debug)]
+pub enum RequestError {
+    /// The body is longer than `MAX_BODY`.
+    TooLarge,
+    /// It isn't a request this API can read.
+    Bad(String),
+    /// Reading or writing the connection failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RequestError::TooLarge => write!(f, "the body is too large"),
+            RequestError::Bad(ref why) => f.write_str(why),
+            RequestError::Io(ref err) => write!(f, "could not read the request: {}", err),
+        }
+    }
+}
+
+impl Error for RequestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            RequestError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RequestError {
+    fn from(err: io::Error) -> RequestError {
+        RequestError::Io(err)
+    }
+}
+
+/// An HTTP request, as far as the API cares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// The path, without any query string.
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn new(method: &str, path: &str, body: &str) -> Request {
+        Request { method: method.to_string(), path: path.to_string(), body: body.into() }
+    }
+}
+
+/// An HTTP response with a JSON body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    fn json<T: ::serde::Serialize>(status: u16, value: &T) -> Response {
+        let body = serde_json::to_string(value).expect("API answers always serialize");
+        Response { status, body }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response::json(status, &ErrorBody { error: message })
+    }
+
+    /// The reason phrase that goes with `status`.
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            204 => "No Content",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+
+    /// Writes the whole response, headers and body.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        write!(out, "HTTP/1.1 {} {}\r\n", self.status, self.reason())?;
+        write!(out, "Content-Type: application/json\r\n")?;
+        write!(out, "Content-Length: {}\r\n", self.body.len())?;
+        // The exercise page may be served from another port.
+        write!(out, "Access-Control-Allow-Origin: *\r\n")?;
+        write!(out, "Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n")?;
+        write!(out, "Access-Control-Allow-Headers: Content-Type\r\n")?;
+        write!(out, "Connection: close\r\n\r\n")?;
+        out.write_all(self.body.as_bytes())?;
+        out.flush()
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+/// The body of `POST /games/{id}/guesses`.
+#[derive(Deserialize)]
+struct GuessBody {
+    guess: u32,
+}
+
+/// The answer to `POST /games`.
+#[derive(Debug, Serialize)]
+struct Created<'a> {
+    id: &'a str,
+    min: u32,
+    max: u32,
+    max_attempts: Option<u32>,
+}
+
+/// The answer to a guess.
+#[derive(Debug, Serialize)]
+struct Guessed {
+    guess: u32,
+    feedback: Feedback,
+    attempts_left: Option<u32>,
+    status: Status,
+    /// Only told once the game is over.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret: Option<u32>,
+}
+
+/// The answer to `GET /games/{id}`.
+#[derive(Debug, Serialize)]
+struct State<'a> {
+    id: &'a str,
+    min: u32,
+    max: u32,
+    max_attempts: Option<u32>,
+    attempts: u32,
+    attempts_left: Option<u32>,
+    guesses: &'a [u32],
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret: Option<u32>,
+}
+
+/// A game and when it was last asked about.
+struct Session {
+    game: Game,
+    last_used: Instant,
+}
+
+/// The games being played, without any networking.
+pub struct Sessions<S> {
+    rules: Rules,
+    source: S,
+    expire_after: Duration,
+    next_id: u64,
+    games: HashMap<String, Session>,
+}
+
+impl<S: SecretSource> Sessions<S> {
+    /// Games get `rules` and secrets from `source`, and are forgotten
+    /// once idle for `expire_after`.
+    pub fn new(rules: Rules, source: S, expire_after: Duration) -> Sessions<S> {
+        Sessions { rules, source, expire_after, next_id: 1, games: HashMap::new() }
+    }
+
+    /// The number of games kept.
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Forgets the games nobody asked about within the timeout.
+    pub fn expire(&mut self, now: Instant) {
+        let expire_after = self.expire_after;
+        self.games.retain(|_, s| now.saturating_duration_since(s.last_used) < expire_after);
+    }
+
+    /// Answers `request`, made at `now`.
+    pub fn handle(&mut self, request: &Request, now: Instant) -> Response {
+        self.expire(now);
+
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+        match (&request.method[..], &segments[..]) {
+            // The preflight a browser sends before a JSON POST.
+            ("OPTIONS", _) => Response { status: 204, body: String::new() },
+            ("POST", ["games"]) => self.create(now),
+            ("GET", ["games", id]) => self.state(id, now),
+            ("POST", ["games", id, "guesses"]) => self.guess(id, &request.body, now),
+            (_, ["games"]) | (_, ["games", _]) | (_, ["games", _, "guesses"]) => {
+                Response::error(405, &format!("{} is not allowed here", request.method))
+            }
+            _ => Response::error(404, &format!("there is nothing at {}", request.path)),
+        }
+    }
+
+    fn create(&mut self, now: Instant) -> Response {
+        let secret = match self.source.next_secret(&self.rules) {
+            Ok(secret) => secret,
+            Err(err) => return Response::error(500, &err.to_string()),
+        };
+        let id = self.next_id.to_string();
+        self.next_id += 1;
+        self.games.insert(id.clone(), Session {
+            game: Game::with_rules(secret, self.rules),
+            last_used: now,
+        });
+
+        Response::json(201, &Created {
+            id: &id,
+            min: self.rules.min,
+            max: self.rules.max,
+            max_attempts: self.rules.max_attempts,
+        })
+    }
+
+    /// The game `id`, marked as used at `now`.
+    fn session(&mut self, id: &str, now: Instant) -> Result<&mut Session, Response> {
+        match self.games.get_mut(id) {
+            Some(session) => {
+                session.last_used = now;
+                Ok(session)
+            }
+            None => Err(Response::error(404, &format!("there is no game {}, or it expired", id))),
+        }
+    }
+
+    fn state(&mut self, id: &str, now: Instant) -> Response {
+        let game = match self.session(id, now) {
+            Ok(session) => &session.game,
+            Err(response) => return response,
+        };
+        let rules = game.rules();
+        Response::json(200, &State {
+            id,
+            min: rules.min,
+            max: rules.max,
+            max_attempts: rules.max_attempts,
+            attempts: game.attempts(),
+            attempts_left: game.attempts_left(),
+            guesses: game.history(),
+            status: game.status(),
+            secret: if game.is_finished() { Some(game.secret()) } else { None },
+        })
+    }
+
+    fn guess(&mut self, id: &str, body: &[u8], now: Instant) -> Response {
+        let game = match self.session(id, now) {
+            Ok(session) => &mut session.game,
+            Err(response) => return response,
+        };
+        let guess = match serde_json::from_slice::<GuessBody>(body) {
+            Ok(body) => body.guess,
+            Err(err) => {
+                return Response::error(400, &format!("expected {{\"guess\": N}}: {}", err))
+            }
+        };
+        if game.is_finished() {
+            return Response::error(409, "the game is over");
+        }
+
+        let feedback = game.guess(guess);
+        Response::json(200, &Guessed {
+            guess,
+            feedback,
+            attempts_left: game.attempts_left(),
+            status: game.status(),
+            secret: if game.is_finished() { Some(game.secret()) } else { None },
+        })
+    }
+}
+
+/// Reads one line of at most `MAX_LINE` bytes into `line`, returning
+/// its length, 0 at the end of `input`.
+fn read_line<R: BufRead>(input: &mut R, line: &mut String) -> Result<usize, RequestError> {
+    match input.take(MAX_LINE as u64 + 1).read_line(line) {
+        Ok(n) if n > MAX_LINE => Err(RequestError::Bad("a line of the request is too long".to_string())),
+        Ok(n) => Ok(n),
+        Err(ref err) if err.kind() == io::ErrorKind::InvalidData => {
+            Err(RequestError::Bad("the request is not UTF-8".to_string()))
+        }
+        Err(err) => Err(RequestError::Io(err)),
+    }
+}
+
+/// Reads one request: the request line, the headers and a body of
+/// `Content-Length` bytes.
+///
+/// # Failures
+/// Returns `RequestError::TooLarge` for a body over `MAX_BODY`,
+/// `RequestError::Bad` for anything else that isn't a request this API
+/// can read, and `RequestError::Io` for errors from `input`.
+pub fn read_request<R: BufRead>(input: &mut R) -> Result<Request, RequestError> {
+    let bad = |message: &str| RequestError::Bad(message.to_string());
+
+    let mut line = String::new();
+    read_line(input, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/") => {
+            (method.to_string(), target.to_string())
+        }
+        _ => return Err(bad("malformed request line")),
+    };
+
+    let mut length = 0;
+    for _ in 0..MAX_HEADERS + 1 {
+        let mut header = String::new();
+        if read_line(input, &mut header)? == 0 {
+            return Err(bad("the headers never ended"));
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            let path = target.split('?').next().unwrap_or("").to_string();
+            let mut body = vec![0; length];
+            input.read_exact(&mut body)?;
+            return Ok(Request { method, path, body });
+        }
+        if let Some(colon) = header.find(':') {
+            if header[..colon].eq_ignore_ascii_case("content-length") {
+                length = header[colon + 1..].trim().parse()
+                    .map_err(|_| bad("Content-Length is not a number"))?;
+                if length > MAX_BODY {
+                    return Err(RequestError::TooLarge);
+                }
+            }
+        }
+    }
+    Err(bad("too many headers"))
+}
+
+/// Answers the one request waiting on `input`, on `output`.
+///
+/// # Failures
+/// Returns errors from reading and writing. A request that can't be read
+/// is still answered, with a 413 if it is too large and a 400 otherwise,
+/// before the error is returned.
+pub fn respond<R, W, S>(input: &mut R, output: &mut W, sessions: &Mutex<Sessions<S>>)
+    -> Result<(), RequestError>
+    where R: BufRead, W: Write, S: SecretSource
+{
+    let response = match read_request(input) {
+        Ok(request) => sessions.lock().unwrap().handle(&request, Instant::now()),
+        Err(err) => {
+            let status = match err {
+                RequestError::TooLarge => Some(413),
+                RequestError::Bad(_) => Some(400),
+                RequestError::Io(_) => None,
+            };
+            if let Some(status) = status {
+                Response::error(status, &err.to_string()).write_to(output)?;
+            }
+            return Err(err);
+        }
+    };
+    Ok(response.write_to(output)?)
+}
+
+/// Answers requests on `listener` forever, one thread per connection.
+pub fn serve<S>(listener: TcpListener, sessions: Sessions<S>) -> io::Result<()>
+    where S: SecretSource + Send + 'static
+{
+    let sessions = Arc::new(Mutex::new(sessions));
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            // One failed connection shouldn't stop the server.
+            Err(_) => continue,
+        };
+        let sessions = sessions.clone();
+        thread::spawn(move || {
+            // A client that stops sending would hold this thread forever.
+            if stream.set_read_timeout(Some(READ_TIMEOUT)).is_err() {
+                return;
+            }
+            let mut output = match stream.try_clone() {
+                Ok(output) => output,
+                Err(_) => return,
+            };
+            let _ = respond(&mut BufReader::new(stream), &mut output, &sessions);
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secret::ListSource;
+    use serde_json::Value;
+    use std::io::Cursor;
+
+    fn sessions(secrets: Vec<u32>) -> Mutex<Sessions<ListSource>> {
+        let rules = Rules { min: 1, max: 10, max_attempts: Some(3) };
+        Mutex::new(Sessions::new(rules, ListSource::new(secrets).unwrap(),
+                                 Duration::from_secs(60)))
+    }
+
+    /// Sends a request through `respond` the way a browser would, and
+    /// returns the raw response.
+    fn exchange(sessions: &Mutex<Sessions<ListSource>>, raw: &str) -> String {
+        let mut output = Vec::new();
+        let _ = respond(&mut Cursor::new(raw), &mut output, sessions);
+        String::from_utf8(output).unwrap()
+    }
+
+    /// The in-process client: the status and JSON body of a request.
+    fn call(sessions: &Mutex<Sessions<ListSource>>, method: &str, path: &str, body: &str)
+        -> (u16, Value)
+    {
+        let raw = format!("{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+                           Content-Length: {}\r\n\r\n{}", method, path, body.len(), body);
+        let response = exchange(sessions, &raw);
+
+        let status = response[9..12].parse().unwrap();
+        let body = &response[response.find("\r\n\r\n").unwrap() + 4..];
+        (status, if body.is_empty() { Value::Null } else { serde_json::from_str(body).unwrap() })
+    }
+
+    #[test]
+    fn play_a_game() {
+        let sessions = sessions(vec![7]);
+        let (status, created) = call(&sessions, "POST", "/games", "");
+        assert_eq!(201, status);
+        assert_eq!(created, serde_json::json!({"id": "1", "min": 1, "max": 10, "max_attempts": 3}));
+
+        let (status, guessed) = call(&sessions, "POST", "/games/1/guesses", r#"{"guess": 5}"#);
+        assert_eq!(200, status);
+        assert_eq!(guessed, serde_json::json!({
+            "guess": 5, "feedback": "TooSmall", "attempts_left": 2, "status": "Playing",
+        }));
+
+        let (_, guessed) = call(&sessions, "POST", "/games/1/guesses", r#"{"guess": 7}"#);
+        assert_eq!(guessed["feedback"], "Correct");
+        assert_eq!(guessed["status"], "Won");
+        assert_eq!(guessed["secret"], 7);
+
+        let (status, state) = call(&sessions, "GET", "/games/1", "");
+        assert_eq!(200, status);
+        assert_eq!(state, serde_json::json!({
+            "id": "1", "min": 1, "max": 10, "max_attempts": 3, "attempts": 2,
+            "attempts_left": 1, "guesses": [5, 7], "status": "Won", "secret": 7,
+        }));
+
+        let (status, error) = call(&sessions, "POST", "/games/1/guesses", r#"{"guess": 7}"#);
+        assert_eq!(409, status);
+        assert_eq!(error["error"], "the game is over");
+    }
+
+    #[test]
+    fn games_are_separate() {
+        let sessions = sessions(vec![3, 9]);
+        call(&sessions, "POST", "/games", "");
+        call(&sessions, "POST", "/games", "");
+
+        let (_, first) = call(&sessions, "POST", "/games/1/guesses", r#"{"guess": 9}"#);
+        let (_, second) = call(&sessions, "POST", "/games/2/guesses", r#"{"guess": 9}"#);
+        assert_eq!(first["feedback"], "TooBig");
+        assert_eq!(second["feedback"], "Correct");
+
+        // The secret stays hidden while the game goes on.
+        let (_, state) = call(&sessions, "GET", "/games/1?fresh=1", "");
+        assert_eq!(state["attempts"], 1);
+        assert!(state.get("secret").is_none());
+    }
+
+    #[test]
+    fn bad_requests() {
+        let sessions = sessions(vec![7]);
+        call(&sessions, "POST", "/games", "");
+
+        assert_eq!(404, call(&sessions, "GET", "/games/2", "").0);
+        assert_eq!(404, call(&sessions, "GET", "/", "").0);
+        assert_eq!(405, call(&sessions, "DELETE", "/games/1", "").0);
+        assert_eq!(204, call(&sessions, "OPTIONS", "/games", "").0);
+
+        let (status, error) = call(&sessions, "POST", "/games/1/guesses", r#"{"guess": "x"}"#);
+        assert_eq!(400, status);
+        assert!(error["error"].as_str().unwrap().starts_with("expected {\"guess\": N}"));
+        assert_eq!(400, call(&sessions, "POST", "/games/1/guesses", "").0);
+
+        assert!(exchange(&sessions, "hello\r\n\r\n").starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        let huge = format!("POST /games HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY + 1);
+        assert!(exchange(&sessions, &huge).starts_with("HTTP/1.1 413 "));
+        match read_request(&mut Cursor::new(huge)) {
+            Err(RequestError::TooLarge) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Lines that never end are cut off instead of read forever.
+        let endless = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert!(exchange(&sessions, &endless).starts_with("HTTP/1.1 400 "));
+        let endless = format!("GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        match read_request(&mut Cursor::new(endless)) {
+            Err(RequestError::Bad(ref why)) => assert_eq!("a line of the request is too long", why),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn out_of_range_guesses_are_free() {
+        let sessions = sessions(vec![7]);
+        call(&sessions, "POST", "/games", "");
+        let (_, guessed) = call(&sessions, "POST", "/games/1/guesses", r#"{"guess": 11}"#);
+        assert_eq!(guessed["feedback"], "OutOfRange");
+        assert_eq!(guessed["attempts_left"], 3);
+    }
+
+    #[test]
+    fn idle_games_expire() {
+        let rules = Rules { min: 1, max: 10, max_attempts: None };
+        let mut sessions = Sessions::new(rules, ListSource::new(vec![4, 5]).unwrap(),
+                                         Duration::from_secs(60));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(201, sessions.handle(&Request::new("POST", "/games", ""), at(0)).status);
+        assert_eq!(201, sessions.handle(&Request::new("POST", "/games", ""), at(30)).status);
+        // Every request keeps its game alive.
+        assert_eq!(200, sessions.handle(&Request::new("GET", "/games/1", ""), at(50)).status);
+
+        sessions.expire(at(100));
+        assert_eq!(1, sessions.len());
+        let gone = sessions.handle(&Request::new("GET", "/games/2", ""), at(100));
+        assert_eq!(Response::error(404, "there is no game 2, or it expired"), gone);
+
+        sessions.expire(at(110));
+        assert!(sessions.is_empty());
+    }
+
+    #[test]
+    fn response_headers() {
+        let mut output = Vec::new();
+        Response::error(404, "gone").write_to(&mut output).unwrap();
+        assert_eq!("HTTP/1.1 404 Not Found\r\n\
+                    Content-Type: application/json\r\n\
+                    Content-Length: 16\r\n\
+                    Access-Control-Allow-Origin: *\r\n\
+                    Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+                    Access-Control-Allow-Headers: Content-Type\r\n\
+                    Connection: close\r\n\r\n\
+                    {\"error\":\"gone\"}", String::from_utf8(output).unwrap());
+    }
+}
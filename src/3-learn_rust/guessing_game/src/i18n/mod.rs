@@ -0,0 +1,387 @@
+//! Message catalogs, so the number game can talk in more than one language.
+//!
+//! Each language is a text file next to this one with a `key = text` line
+//! per message, built into the binary. Texts name what the game fills in
+//! with braces: `You guessed: {guess}`. The English catalog is the
+//! reference: a message missing from another catalog falls back to it,
+//! and the tests fail if any catalog misses a key or a placeholder.
+//!
+//! The `Display` impls of the game's own types (`InputError`, `Clue`,
+//! `Scorecard`, `Contradiction`, ...) use the English catalog, so there is
+//! one place for every text. Every game and the multiplayer client talk
+//! through a catalog. What stays English is meant for whoever runs the
+//! program rather than for a player: the command line with its usage and
+//! errors, the server's own protocol and status lines, the leaderboard
+//! table and the reasons `--adaptive` gives.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use bench::Summary;
+use hints::{Clue, Hint, HintError, Hints, Scorecard, GUESS_PENALTY, NEAR_DISTANCE};
+use input::InputError;
+use puzzle::mastermind::{self, CodeError, PEGS};
+use reverse::Contradiction;
+use timeattack::{TimeScore, POINTS_PER_SECOND};
+
+/// Every language there is a catalog for, English first.
+pub const LANGUAGES: [(&str, &str); 4] = [
+    ("en", include_str!("en.txt")),
+    ("de", include_str!("de.txt")),
+    ("fr", include_str!("fr.txt")),
+    ("es", include_str!("es.txt")),
+];
+
+/// The messages of one language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    lang: &'static str,
+    messages: HashMap<&'static str, &'static str>,
+}
+
+/// Parses the lines of a catalog file.
+///
+/// # Panics
+/// Panics on a line that is neither blank, a `#` comment nor `key = text`.
+/// Catalogs are built in, so that is a bug the tests catch.
+fn parse(lang: &str, text: &'static str) -> HashMap<&'static str, &'static str> {
+    let mut messages = HashMap::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.find('=') {
+            Some(at) => {
+                messages.insert(line[..at].trim(), line[at + 1..].trim());
+            }
+            None => panic!("line {} of the {} catalog has no '='", i + 1, lang),
+        }
+    }
+    messages
+}
+
+impl Catalog {
+    /// The catalog for the language code `lang`, such as "de".
+    pub fn new(lang: &str) -> Option<Catalog> {
+        LANGUAGES.iter()
+            .find(|&&(code, _)| code == lang)
+            .map(|&(code, text)| Catalog { lang: code, messages: parse(code, text) })
+    }
+
+    /// The English catalog, which every other one falls back to.
+    pub fn english() -> &'static Catalog {
+        static ENGLISH: OnceLock<Catalog> = OnceLock::new();
+        ENGLISH.get_or_init(|| Catalog::new("en").expect("there is an English catalog"))
+    }
+
+    /// Picks the catalog for `--lang` if it was given, otherwise for the
+    /// locale of the environment, and English if neither is available.
+    pub fn choose(flag: Option<&str>) -> Catalog {
+        flag.map(|lang| lang.to_string())
+            .or_else(env_lang)
+            .and_then(|lang| Catalog::new(&lang))
+            .unwrap_or_else(|| Catalog::english().clone())
+    }
+
+    pub fn lang(&self) -> &str {
+        self.lang
+    }
+
+    /// The text of `key`, placeholders and all. A key no catalog has
+    /// comes back as it is.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        if let Some(text) = self.messages.get(key) {
+            return text;
+        }
+        let english = Catalog::english();
+        match english.messages.get(key) {
+            Some(text) if self.lang != english.lang => text,
+            _ => {
+                debug_assert!(false, "no message '{}'", key);
+                key
+            }
+        }
+    }
+
+    /// The text of `key` with each `{name}` replaced by the value given
+    /// for it in `args`. Placeholders without a value are left as they are.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(key);
+        let mut out = String::with_capacity(text.len());
+
+        while let Some(open) = text.find('{') {
+            out.push_str(&text[..open]);
+            let rest = &text[open..];
+            let close = match rest.find('}') {
+                Some(close) => close,
+                None => break,
+            };
+            match args.iter().find(|&&(name, _)| name == &rest[1..close]) {
+                Some(&(_, value)) => out.push_str(&value.to_string()),
+                None => out.push_str(&rest[..close + 1]),
+            }
+            text = &rest[close + 1..];
+        }
+        out.push_str(text);
+        out
+    }
+
+    /// What to tell the player about a line that gave no guess.
+    pub fn input_error(&self, err: &InputError) -> String {
+        match *err {
+            InputError::Io(ref err) => self.format("read_failed", &[("error", err)]),
+            InputError::Eof => self.get("no_more_input").to_string(),
+            InputError::NotANumber(ref line) if line.is_empty() => {
+                self.get("empty_line").to_string()
+            }
+            InputError::NotANumber(ref line) => self.format("not_a_number", &[("line", line)]),
+            InputError::Negative(ref line) => self.format("negative", &[("line", line)]),
+            InputError::TooBig(ref line) => {
+                self.format("too_big_number", &[("line", line), ("max", &u32::MAX)])
+            }
+        }
+    }
+
+    /// What a hint revealed.
+    pub fn clue(&self, clue: &Clue) -> String {
+        match *clue {
+            Clue::Warmer => self.get("clue_warmer").to_string(),
+            Clue::Colder => self.get("clue_colder").to_string(),
+            Clue::Same => self.get("clue_same").to_string(),
+            Clue::Even => self.get("clue_even").to_string(),
+            Clue::Odd => self.get("clue_odd").to_string(),
+            Clue::Divisible { by, yes } => {
+                self.format(if yes { "clue_divisible" } else { "clue_not_divisible" }, &[("by", &by)])
+            }
+            Clue::Near { guess, yes } => {
+                self.format(if yes { "clue_near" } else { "clue_not_near" },
+                            &[("distance", &NEAR_DISTANCE), ("guess", &guess)])
+            }
+        }
+    }
+
+    /// Why a hint was refused.
+    pub fn hint_error(&self, err: &HintError) -> String {
+        match *err {
+            HintError::NotAllowed(hint) => self.format("hint_not_allowed", &[("name", &hint.name())]),
+            HintError::TooEarly(Hint::Warmer) => self.get("hint_needs_two").to_string(),
+            HintError::TooEarly(hint) => self.format("hint_needs_one", &[("name", &hint.name())]),
+            HintError::UsedUp(hint) => self.format("hint_used_up", &[("name", &hint.name())]),
+        }
+    }
+
+    /// The hints that can be bought, and their prices.
+    pub fn menu(&self, hints: &Hints) -> String {
+        if hints.allowed().is_empty() {
+            return self.get("no_hints").to_string();
+        }
+        let items: Vec<String> = hints.allowed().iter()
+            .map(|h| self.format("hint_item", &[("name", &h.name()), ("cost", &h.cost())]))
+            .collect();
+        self.format("hint_menu", &[("hints", &items.join(", "))])
+    }
+
+    /// How the points of a game add up.
+    pub fn scorecard(&self, card: &Scorecard) -> String {
+        if !card.won {
+            return self.get("score_lost").to_string();
+        }
+        self.format("score", &[
+            ("base", &card.base),
+            ("penalty", &(card.extra_guesses * GUESS_PENALTY)),
+            ("extra", &card.extra_guesses),
+            ("hints", &card.hint_cost),
+            ("total", &card.total()),
+        ])
+    }
+
+    /// How the points of a timed game add up.
+    pub fn time_score(&self, score: &TimeScore) -> String {
+        if !score.won {
+            return self.get("score_lost").to_string();
+        }
+        self.format("time_score", &[
+            ("base", &score.base),
+            ("penalty", &(score.extra_guesses * GUESS_PENALTY)),
+            ("extra", &score.extra_guesses),
+            ("bonus", &(score.seconds_left * POINTS_PER_SECOND)),
+            ("seconds", &score.seconds_left),
+            ("total", &score.total()),
+        ])
+    }
+
+    /// Why a line is not a Mastermind code.
+    pub fn code_error(&self, err: &CodeError) -> String {
+        match *err {
+            CodeError::Length => self.format("mm_bad_length", &[("pegs", &PEGS)]),
+            CodeError::NotAColour(letter) => {
+                let colours = self.format("mm_colours", &[("colours", &mastermind::colour_letters())]);
+                self.format("mm_not_a_colour", &[("letter", &letter), ("colours", &colours)])
+            }
+        }
+    }
+
+    /// Which answers of a reverse game can't all be true.
+    pub fn contradiction(&self, contradiction: &Contradiction) -> String {
+        match (contradiction.too_small, contradiction.too_big) {
+            (Some(low), Some(high)) => {
+                self.format("contradiction_between", &[("low", &low), ("high", &high)])
+            }
+            (Some(low), None) => {
+                self.format("contradiction_above", &[("low", &low), ("max", &contradiction.max)])
+            }
+            (None, Some(high)) => {
+                self.format("contradiction_below", &[("high", &high), ("min", &contradiction.min)])
+            }
+            (None, None) => self.get("contradiction").to_string(),
+        }
+    }
+
+    /// How a strategy did in a bench: a line of statistics and a histogram.
+    pub fn summary(&self, summary: &Summary) -> String {
+        let mut text = self.format("bench_summary", &[
+            ("strategy", &summary.strategy),
+            ("min", &summary.min()),
+            ("mean", &format!("{:.2}", summary.mean())),
+            ("max", &summary.max()),
+        ]);
+        text.push('\n');
+        text.push_str(&summary.histogram());
+        text
+    }
+}
+
+/// The language code of the environment's locale: the first of
+/// `LC_ALL`, `LC_MESSAGES` and `LANG` that is set, cut down from
+/// "de_DE.UTF-8" to "de".
+pub fn env_lang() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map(|locale| lang_code(&locale).to_string())
+}
+
+/// The language part of a locale name.
+pub fn lang_code(locale: &str) -> &str {
+    locale.split(['_', '.', '@']).next().unwrap_or(locale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// The placeholder names in `text`.
+    fn placeholders(text: &str) -> BTreeSet<&str> {
+        text.split('{').skip(1).filter_map(|part| part.split('}').next()).collect()
+    }
+
+    /// Everything that talks to the player through a catalog.
+    const SOURCES: [(&str, &str); 9] = [
+        ("main.rs", include_str!("../main.rs")),
+        ("client.rs", include_str!("../client.rs")),
+        ("timeattack.rs", include_str!("../timeattack.rs")),
+        ("tui.rs", include_str!("../tui.rs")),
+        ("i18n/mod.rs", include_str!("mod.rs")),
+        ("puzzle/mod.rs", include_str!("../puzzle/mod.rs")),
+        ("puzzle/number.rs", include_str!("../puzzle/number.rs")),
+        ("puzzle/mastermind.rs", include_str!("../puzzle/mastermind.rs")),
+        ("puzzle/words.rs", include_str!("../puzzle/words.rs")),
+    ];
+
+    /// The keys `source` looks up with a literal, as in `.get("title")`
+    /// or `.format("range", ...)`.
+    fn keys_used(source: &str) -> BTreeSet<&str> {
+        let mut keys = BTreeSet::new();
+        for call in &[".get(\"", ".format(\""] {
+            for part in source.split(call).skip(1) {
+                keys.extend(part.split('"').next());
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn every_language_has_every_message() {
+        let english = Catalog::english();
+        for &(lang, _) in &LANGUAGES[1..] {
+            let catalog = Catalog::new(lang).unwrap();
+
+            let missing: Vec<&&str> = english.messages.keys()
+                .filter(|key| !catalog.messages.contains_key(*key))
+                .collect();
+            assert!(missing.is_empty(), "the {} catalog is missing {:?}", lang, missing);
+
+            let extra: Vec<&&str> = catalog.messages.keys()
+                .filter(|key| !english.messages.contains_key(*key))
+                .collect();
+            assert!(extra.is_empty(), "the {} catalog has unknown keys {:?}", lang, extra);
+
+            for (key, text) in &catalog.messages {
+                assert_eq!(placeholders(english.messages[key]), placeholders(text),
+                           "the {} text of '{}' has other placeholders than the English one",
+                           lang, key);
+            }
+        }
+    }
+
+    #[test]
+    fn every_message_in_use_is_there() {
+        let english = Catalog::english();
+        for &(file, source) in &SOURCES {
+            let missing: Vec<&str> = keys_used(source).into_iter()
+                .filter(|key| !english.messages.contains_key(key))
+                .collect();
+            assert!(missing.is_empty(), "{} uses messages no catalog has: {:?}", file, missing);
+        }
+        // The scan itself works, and found the other modes.
+        assert!(keys_used(SOURCES[0].1).contains("reverse_guess"));
+        assert!(keys_used(SOURCES[1].1).contains("client_welcome"));
+        assert!(keys_used(SOURCES[7].1).contains("mm_title"));
+    }
+
+    #[test]
+    fn formatting() {
+        let english = Catalog::english();
+        assert_eq!("You guessed: 42", english.format("guessed", &[("guess", &42)]));
+        assert_eq!("Out of range! Guess between 1 and {max}.",
+                   english.format("out_of_range", &[("min", &1)]));
+
+        let german = Catalog::new("de").unwrap();
+        assert_eq!("de", german.lang());
+        assert_eq!("Dein Tipp: 42", german.format("guessed", &[("guess", &42)]));
+        assert_eq!("Punkte: 0, ein verlorenes Spiel zählt nichts.", german.get("score_lost"));
+    }
+
+    #[test]
+    fn fallback_to_english() {
+        let mut french = Catalog::new("fr").unwrap();
+        french.messages.remove("too_big");
+        assert_eq!("Too big!", french.get("too_big"));
+        assert_eq!("Trop petit !", french.get("too_small"));
+    }
+
+    #[test]
+    fn choosing() {
+        assert!(Catalog::new("xx").is_none());
+        assert_eq!("es", Catalog::choose(Some("es")).lang());
+        assert_eq!("de", lang_code("de_DE.UTF-8"));
+        assert_eq!("fr", lang_code("fr"));
+        assert_eq!("C", lang_code("C.UTF-8"));
+    }
+
+    #[test]
+    fn typed_messages() {
+        let spanish = Catalog::new("es").unwrap();
+        assert_eq!("'abc' no es un número.",
+                   spanish.input_error(&InputError::NotANumber("abc".to_string())));
+        assert_eq!("El número no es divisible por 3.",
+                   spanish.clue(&Clue::Divisible { by: 3, yes: false }));
+        assert_eq!("Pistas: hint parity (-10)", spanish.menu(&Hints::new(&[Hint::Parity])));
+        assert_eq!("La pista warmer compara tus dos últimos números, di dos primero.",
+                   spanish.hint_error(&HintError::TooEarly(Hint::Warmer)));
+    }
+}
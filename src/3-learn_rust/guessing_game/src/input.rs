@@ -0,0 +1,151 @@
+//! Reading guesses from the player.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::num::IntErrorKind;
+
+use i18n::Catalog;
+
+/// Why a line of input did not give us a guess.
+#[derive(Debug)]
+pub enum InputError {
+    /// Reading from the input failed.
+    Io(io::Error),
+    /// The input was closed, there will be no more guesses.
+    Eof,
+    /// The line was not a number at all. Holds the trimmed line.
+    NotANumber(String),
+    /// The line was a negative number. Holds the trimmed line.
+    Negative(String),
+    /// The line was a number too big to fit a `u32`. Holds the trimmed line.
+    TooBig(String),
+}
+
+impl InputError {
+    /// Whether the player can simply try again.
+    ///
+    /// `Io` and `Eof` mean the input is gone, everything else was just
+    /// a bad line.
+    pub fn is_recoverable(&self) -> bool {
+        !matches!(*self, InputError::Io(_) | InputError::Eof)
+    }
+}
+
+impl fmt::Display for InputError {
+    /// The message shown to the player, in English. See `Catalog::input_error`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::english().input_error(self))
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            InputError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for InputError {
+    fn from(err: io::Error) -> InputError {
+        InputError::Io(err)
+    }
+}
+
+/// Turns one line of input into a guess.
+pub fn parse_guess(line: &str) -> Result<u32, InputError> {
+    let line = line.trim();
+
+    line.parse().map_err(|err: ::std::num::ParseIntError| {
+        let digits = line.strip_prefix(['+', '-']).unwrap_or(line);
+        let numeric = !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit());
+
+        if numeric && line.starts_with('-') {
+            InputError::Negative(line.to_string())
+        } else if *err.kind() == IntErrorKind::PosOverflow {
+            InputError::TooBig(line.to_string())
+        } else {
+            InputError::NotANumber(line.to_string())
+        }
+    })
+}
+
+/// Reads one line from `reader`, line ending included.
+///
+/// # Failures
+/// Returns `InputError::Eof` once the input is closed, instead of handing
+/// back an empty line.
+pub fn read_line<R: BufRead>(reader: &mut R) -> Result<String, InputError> {
+    let mut line = String::new();
+
+    // read_line() returns the number of bytes read. Zero means the
+    // input is closed.
+    if reader.read_line(&mut line)? == 0 {
+        return Err(InputError::Eof);
+    }
+
+    Ok(line)
+}
+
+/// Reads one line from `reader` and parses it as a guess.
+///
+/// # Failures
+/// See `read_line` and `parse_guess`.
+pub fn read_guess<R: BufRead>(reader: &mut R) -> Result<u32, InputError> {
+    parse_guess(&read_line(reader)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(42, parse_guess("42\n").unwrap());
+        assert_eq!(7, parse_guess("  7 ").unwrap());
+        assert_eq!(u32::MAX, parse_guess("4294967295").unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        match parse_guess("abc") {
+            Err(InputError::NotANumber(ref s)) if s == "abc" => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse_guess("\n") {
+            Err(InputError::NotANumber(ref s)) if s.is_empty() => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse_guess("-5") {
+            Err(InputError::Negative(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse_guess("4294967296") {
+            Err(InputError::TooBig(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse_guess("--5") {
+            Err(InputError::NotANumber(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn eof_is_reported() {
+        let mut input = Cursor::new("12\n");
+        assert_eq!(12, read_guess(&mut input).unwrap());
+        match read_guess(&mut input) {
+            Err(InputError::Eof) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn recoverable() {
+        assert!(InputError::NotANumber(String::new()).is_recoverable());
+        assert!(!InputError::Eof.is_recoverable());
+    }
+}
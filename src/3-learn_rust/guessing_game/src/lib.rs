@@ -0,0 +1,45 @@
+//! The rules of the guessing game, kept apart from any input or output.
+//!
+//! The binary in `main.rs` is just one front-end: it reads lines from
+//! stdin and prints the feedback. Anything else that wants to play the
+//! game (tests, other front-ends) drives the same `Game` type.
+//!
+//! # Examples
+//!
+//! ```
+//! use guessing_game::{Game, Feedback};
+//!
+//! let mut game = Game::new(42);
+//! assert_eq!(Feedback::TooSmall, game.guess(10));
+//! assert_eq!(Feedback::Correct, game.guess(42));
+//! assert!(game.is_finished());
+//! ```
+
+extern crate rand;
+extern crate ratatui;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+pub mod adaptive;
+pub mod bench;
+pub mod client;
+pub mod difficulty;
+pub mod game;
+pub mod hints;
+pub mod http;
+pub mod i18n;
+pub mod input;
+pub mod options;
+pub mod puzzle;
+pub mod record;
+pub mod reverse;
+pub mod scores;
+pub mod secret;
+pub mod server;
+pub mod solver;
+pub mod timeattack;
+pub mod tui;
+
+pub use game::{Feedback, Game, Rules, Status};
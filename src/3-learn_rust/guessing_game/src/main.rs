@@ -1,45 +1,477 @@
 // Make use of an external dependency called "rand"
 extern crate rand; // no need to "use rand;", this is done here.
+// The game rules live in our own library crate (src/lib.rs).
+extern crate guessing_game;
+extern crate ratatui;
 
-use std::io;
-use std::cmp::Ordering;
-use rand::Rng;
+use std::env;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use guessing_game::{Game, Rules, Status};
+use guessing_game::adaptive;
+use guessing_game::bench;
+use guessing_game::difficulty::{self, Difficulty};
+use guessing_game::hints::{Hint, Hints, Scorecard};
+use guessing_game::http::{self, Sessions};
+use guessing_game::i18n::Catalog;
+use guessing_game::puzzle::{self, Mastermind, NumberPuzzle, Puzzle, WordPuzzle};
+use guessing_game::puzzle::mastermind;
+use guessing_game::puzzle::words::{self, WordList};
+use guessing_game::client;
+use guessing_game::input::{self, InputError};
+use guessing_game::options::{self, Command, Options, USAGE};
+use guessing_game::scores::{self, Score};
+use guessing_game::record::{self, RecordError, Recorder};
+use guessing_game::reverse::{Answer, ReverseGame, Verdict};
+use guessing_game::secret::{self, SecretSource, SourceSpec};
+use guessing_game::server::{self, Lobby};
+use guessing_game::timeattack::{self, TimeScore};
+use guessing_game::tui::{self, App};
+use guessing_game::solver::{BinarySearch, BisectWrongSide, LinearScan, RandomGuess, Strategy};
+
+// Exit statuses, so scripts can tell how a game ended.
+// Keep these in sync with the list at the end of USAGE.
+const EXIT_WIN: i32 = 0;
+const EXIT_LOSS: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_EOF: i32 = 3;
+const EXIT_IO: i32 = 4;
+
+// How many secrets --self-test draws.
+const SELF_TEST_DRAWS: u64 = 100_000;
 
 fn main() {
-    println!("Guess the number!");
+    // Skip the first argument, it is the program name.
+    let options = match options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            println!("error: {}\n\n{}", message, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if options.self_test {
+        self_test(&options);
+        return;
+    }
+
+    match options.command {
+        Command::Play => play(&options),
+        Command::Bench { games } => run_bench(&options, games),
+        Command::Reverse => reverse(&options),
+        Command::Scores { top, ref filter } => show_scores(&options, top, filter),
+        Command::Serve { port } => serve(&options, port),
+        Command::Client { ref host, port } => join(&options, host, port),
+        Command::Http { port, expire_secs } => serve_http(&options, port, expire_secs),
+        Command::Mastermind => mastermind(&options),
+        Command::Words => words(&options),
+        Command::Replay { ref file, speed } => replay(file, speed),
+        Command::TimeAttack { seconds } => time_attack(&options, seconds),
+    }
+}
+
+/// Prints an error about the setup of the game and quits.
+fn fail(err: &dyn Display) -> ! {
+    println!("error: {}", err);
+    process::exit(EXIT_USAGE);
+}
+
+fn open_source(spec: &SourceSpec) -> Box<dyn SecretSource + Send> {
+    spec.open().unwrap_or_else(|err| fail(&err))
+}
 
-    let secret_number = rand::thread_rng().gen_range(1, 101);
+/// Checks that the secret source is fair instead of playing.
+fn self_test(options: &Options) {
+    let mut source = open_source(&options.source);
+    let result = secret::chi_square(&mut source, &options.rules, SELF_TEST_DRAWS)
+        .unwrap_or_else(|err| fail(&err));
+
+    println!("{}", result);
+    if !result.is_uniform() {
+        process::exit(EXIT_LOSS);
+    }
+}
+
+/// A game against the computer, on stdin and stdout.
+fn play(options: &Options) {
+    let rules = if options.adaptive { adaptive_rules(options) } else { options.rules };
+    let mut source = open_source(&options.source);
+
+    // With --seed every run plays the same game, which is what tests need.
+    let secret_number = source.next_secret(&rules).unwrap_or_else(|err| fail(&err));
 
     // println!("The secret number is: {}", secret_number);
 
+    let game = Game::with_rules(secret_number, rules);
+    let hints = Hints::new(options.difficulty.map_or(&Hint::ALL[..], Difficulty::allowed_hints));
+    let catalog = Catalog::choose(options.lang.as_deref());
+    let mut number = NumberPuzzle::new(game, hints).with_catalog(catalog.clone());
+    let started = Instant::now();
+
+    let status = if options.tui {
+        number = play_tui(number);
+        // The full screen is gone, say how it ended on the normal one.
+        // Giving up with Esc counts as a loss.
+        let game = number.game();
+        if game.status() == Status::Won {
+            println!("{}", catalog.format("won_in", &[("attempts", &game.attempts())]));
+            Status::Won
+        } else {
+            println!("{}", catalog.format("lose", &[("reveal", &number.reveal())]));
+            Status::Lost
+        }
+    } else if let Some(ref path) = options.record {
+        let (played, status) = play_recorded(number, path);
+        number = played;
+        status
+    } else {
+        run_puzzle(&mut number)
+    };
+    let game = number.game();
+
+    let base = options.difficulty.map_or(difficulty::CUSTOM_BASE_POINTS, Difficulty::base_points);
+    let scorecard = Scorecard::new(base, game, number.hints());
+    println!("{}", catalog.scorecard(&scorecard));
+
+    save_score(options, Score {
+        player: player_name(options),
+        min: rules.min,
+        max: rules.max,
+        max_attempts: rules.max_attempts,
+        difficulty: options.difficulty.map(|d| d.name().to_string()),
+        attempts: game.attempts(),
+        points: Some(scorecard.total()),
+        won: status == Status::Won,
+        elapsed_secs: started.elapsed().as_secs_f64(),
+        finished_at: scores::now(),
+    });
+
+    process::exit(exit_status(status));
+}
+
+/// The rules `--adaptive` picked from the player's history, after
+/// saying why.
+fn adaptive_rules(options: &Options) -> Rules {
+    let player = player_name(options);
+    let history: Vec<Score> = scores::load(&scores_path(options))
+        .unwrap_or_else(|err| {
+            println!("warning: playing as if for the first time: {}", err);
+            Vec::new()
+        })
+        .into_iter()
+        .filter(|score| score.player == player)
+        .collect();
+
+    let plan = adaptive::plan(&history);
+    println!("{}\n", plan);
+    plan.rules
+}
+
+/// The number game against a clock of `seconds`. Hints would only eat
+/// into the time, so there are none.
+fn time_attack(options: &Options, seconds: u64) {
+    let rules = options.rules;
+    let mut source = open_source(&options.source);
+    let secret_number = source.next_secret(&rules).unwrap_or_else(|err| fail(&err));
+    let catalog = Catalog::choose(options.lang.as_deref());
+    let mut number = NumberPuzzle::new(Game::with_rules(secret_number, rules), Hints::new(&[]))
+        .with_catalog(catalog.clone());
+
+    // One deadline for the countdown and the game, so they can't drift
+    // apart.
+    let deadline = Instant::now().checked_add(Duration::from_secs(seconds))
+        .expect("--seconds is at most an hour");
+    let (sender, events) = mpsc::channel();
+    timeattack::spawn_timer(deadline, Duration::from_secs(1), sender.clone());
+    // The reader may still be stuck in read_line when the game is over.
+    // It is never joined: exiting the process ends it.
+    timeattack::spawn_reader(BufReader::new(io::stdin()), sender);
+
+    let started = Instant::now();
+    let stdout = io::stdout();
+    let outcome = timeattack::play(&mut number, deadline, &events, &mut stdout.lock())
+        .unwrap_or_else(|err| input_gone(err));
+    let game = number.game();
+
+    let base = options.difficulty.map_or(difficulty::CUSTOM_BASE_POINTS, Difficulty::base_points);
+    let score = TimeScore::new(base, game.attempts(), &outcome);
+    println!("{}", catalog.time_score(&score));
+
+    save_score(options, Score {
+        player: player_name(options),
+        min: rules.min,
+        max: rules.max,
+        max_attempts: rules.max_attempts,
+        difficulty: options.difficulty.map(|d| d.name().to_string()),
+        attempts: game.attempts(),
+        points: Some(score.total()),
+        won: outcome.status == Status::Won,
+        elapsed_secs: started.elapsed().as_secs_f64(),
+        finished_at: scores::now(),
+    });
+
+    process::exit(exit_status(outcome.status));
+}
+
+/// Plays `number` on stdin and stdout like `run_puzzle`, saving the game
+/// to `path` as it goes.
+fn play_recorded(number: NumberPuzzle, path: &Path) -> (NumberPuzzle, Status) {
+    let file = File::create(path)
+        .unwrap_or_else(|err| fail(&format!("could not create {}: {}", path.display(), err)));
+    let mut recorder = Recorder::new(number, file).unwrap_or_else(|err| fail(&err));
+
+    let status = run_puzzle(&mut recorder);
+    let (number, result) = recorder.finish(status);
+    if let Err(err) = result {
+        println!("warning: the recording is incomplete: {}", err);
+    }
+    (number, status)
+}
+
+/// Plays back a recording, checking that the game answers the same way.
+/// It talks in the language the game was recorded in.
+fn replay(file: &Path, speed: u32) {
+    let events = record::load_file(file).unwrap_or_else(|err| fail(&err));
+    let catalog = record::catalog(&events);
+    let stdout = io::stdout();
+
+    match record::replay(&events, speed, &mut stdout.lock()) {
+        Ok(status) => {
+            println!("{}", match status {
+                Status::Won => catalog.get("replay_won"),
+                Status::Lost => catalog.get("replay_lost"),
+                Status::Playing => catalog.get("replay_unfinished"),
+            });
+        }
+        Err(err @ RecordError::Mismatch { .. }) => {
+            println!("{}", catalog.format("replay_differs", &[("error", &err)]));
+            process::exit(EXIT_LOSS);
+        }
+        Err(RecordError::Io(err)) => {
+            println!("{}", err);
+            process::exit(EXIT_IO);
+        }
+        Err(err) => fail(&err),
+    }
+}
+
+/// Plays `number` full screen, until it is over or the player presses Esc.
+fn play_tui(number: NumberPuzzle) -> NumberPuzzle {
+    let mut app = App::new(number);
+    let result = ratatui::try_init().and_then(|mut terminal| tui::run(&mut terminal, &mut app));
+    ratatui::restore();
+
+    if let Err(err) = result {
+        println!("error: the full screen needs a terminal: {}", err);
+        process::exit(EXIT_IO);
+    }
+    app.into_number()
+}
+
+/// Cracking a code of coloured pegs.
+fn mastermind(options: &Options) {
+    let attempts = options.rules.max_attempts.unwrap_or(mastermind::DEFAULT_ATTEMPTS);
+    let mut source = open_source(&options.source);
+    let mut game = Mastermind::draw(&mut source, attempts).unwrap_or_else(|err| fail(&err))
+        .with_catalog(Catalog::choose(options.lang.as_deref()));
+
+    process::exit(exit_status(run_puzzle(&mut game)));
+}
+
+/// Guessing a word from the word list.
+fn words(options: &Options) {
+    let list = match options.words_file {
+        Some(ref path) => WordList::from_file(path).unwrap_or_else(|err| fail(&err)),
+        None => WordList::builtin(),
+    };
+    let attempts = options.rules.max_attempts.unwrap_or(words::DEFAULT_ATTEMPTS);
+    let mut source = open_source(&options.source);
+    let mut game = WordPuzzle::draw(&mut source, list, attempts).unwrap_or_else(|err| fail(&err))
+        .with_catalog(Catalog::choose(options.lang.as_deref()));
+
+    process::exit(exit_status(run_puzzle(&mut game)));
+}
+
+/// Plays `puzzle` on stdin and stdout. This binary is only the IO shell:
+/// the shared loop in `puzzle::play` reads the lines and prints what the
+/// puzzle answers.
+fn run_puzzle<P: Puzzle>(puzzle: &mut P) -> Status {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    // The input is gone, there is no point in asking again.
+    puzzle::play(puzzle, &mut stdin.lock(), &mut stdout.lock())
+        .unwrap_or_else(|err| input_gone(err))
+}
+
+fn exit_status(status: Status) -> i32 {
+    if status == Status::Won { EXIT_WIN } else { EXIT_LOSS }
+}
+
+fn scores_path(options: &Options) -> PathBuf {
+    options.scores_file.clone().unwrap_or_else(scores::default_path)
+}
+
+/// The name given with --name, or else the login name.
+fn player_name(options: &Options) -> String {
+    options.player.clone()
+        .or_else(|| env::var("USER").ok())
+        .or_else(|| env::var("USERNAME").ok())
+        .unwrap_or_else(|| "anonymous".to_string())
+}
+
+/// Adds a finished game to the leaderboard. Failing to do so is not worth
+/// spoiling the game over, so it only prints a warning.
+fn save_score(options: &Options, score: Score) {
+    if let Err(err) = scores::record(&scores_path(options), score) {
+        println!("warning: your score was not saved: {}", err);
+    }
+}
+
+/// Prints the leaderboard.
+fn show_scores(options: &Options, top: usize, filter: &scores::Filter) {
+    let all = scores::load(&scores_path(options)).unwrap_or_else(|err| fail(&err));
+    print!("{}", scores::format_table(&scores::top(&all, filter, top)));
+}
+
+/// Exits after the input is gone, with a status telling why.
+fn input_gone(err: InputError) -> ! {
+    println!("{}", err);
+    process::exit(match err {
+        InputError::Eof => EXIT_EOF,
+        _ => EXIT_IO,
+    });
+}
+
+/// The computer guesses a number the player thinks of.
+fn reverse(options: &Options) {
+    let rules = options.rules;
+    let catalog = Catalog::choose(options.lang.as_deref());
+    let mut game = ReverseGame::new(rules);
+
+    println!("{}", catalog.format("reverse_intro", &[("min", &rules.min), ("max", &rules.max)]));
+    println!("{}", catalog.get("reverse_how"));
+
+    let stdin = io::stdin();
+    let mut stdin = stdin.lock();
+
     loop {
-        println!("Please input your guess.");
-
-        // Create a mutable binding to a new String.
-        let mut guess = String::new();
-
-        // read_line takes a mutable string (&mut String) as an arg.
-        // i.e. ("&mut guess" instead of "&guess")
-        io::stdin().read_line(&mut guess)
-            // read_line() returns an io::Result. io::Result has an ok() method.
-            .ok() // Assume this value is a successful one. If not, ignore error info.
-            // ok() returns a type with an expect() method.
-            .expect("Failed to read line");
-
-        let guess: u32 = match guess.trim().parse() {
-            Ok(num) => num, // Right hand side becomes the number that got parsed.
-            Err(_) => continue, // Use "_" to indicate we are ignoring this value.
-        };
+        println!("{}", catalog.format("reverse_guess", &[("guess", &game.guess())]));
 
-        println!("You guessed: {}", guess);
+        let line = input::read_line(&mut stdin).unwrap_or_else(|err| input_gone(err));
+        let answer = match Answer::parse(&line) {
+            Some(answer) => answer,
+            None => {
+                println!("{}", catalog.get("reverse_bad_answer"));
+                continue;
+            }
+        };
 
-        match guess.cmp(&secret_number) {
-            Ordering::Less    => println!("Too small!"),
-            Ordering::Greater => println!("Too big!"),
-            Ordering::Equal   => {
-                println!("You win!");
-                break;
+        match game.answer(answer) {
+            Verdict::Continue => {}
+            Verdict::Found(number) => {
+                println!("{}", catalog.format("reverse_found",
+                                              &[("guesses", &game.guesses()), ("number", &number)]));
+                process::exit(EXIT_WIN);
             }
+            Verdict::Cheated(contradiction) => {
+                let contradiction = catalog.contradiction(&contradiction);
+                println!("{}", catalog.format("reverse_cheated", &[("contradiction", &contradiction)]));
+                process::exit(EXIT_LOSS);
+            }
+        }
+    }
+}
+
+/// Lets every solver strategy play `games` games and prints how they did.
+fn run_bench(options: &Options, games: u32) {
+    let rules = options.rules;
+    let catalog = Catalog::choose(options.lang.as_deref());
+    let bound = bench::binary_search_bound(&rules);
+
+    println!("{}", catalog.format("bench_games",
+                                  &[("games", &games), ("min", &rules.min), ("max", &rules.max)]));
+    println!("{}", catalog.format("bench_bound",
+                                  &[("numbers", &(rules.max - rules.min + 1)), ("bound", &bound)]));
+    println!();
+
+    // The random strategy gets its own generator. With --seed the whole
+    // benchmark is repeatable. It must not share the seed of the secrets,
+    // or its first guess would always be right.
+    let rng_seed = match options.source {
+        SourceSpec::Seeded(seed) => seed.wrapping_add(1),
+        _ => rand::random(),
+    };
+    let mut strategies: Vec<Box<dyn Strategy>> = vec![
+        Box::new(BinarySearch::new()),
+        Box::new(RandomGuess::new(secret::seeded_rng(rng_seed))),
+        Box::new(LinearScan::new()),
+        Box::new(BisectWrongSide::new()),
+    ];
+
+    for strategy in &mut strategies {
+        // A fresh source for every strategy, so that seeded and file
+        // sources hand each one the same secrets.
+        let mut source = open_source(&options.source);
+        let summary = bench::run(&mut **strategy, &mut source, &rules, games)
+            .unwrap_or_else(|err| fail(&err));
+
+        println!("{}", catalog.summary(&summary));
+        if summary.strategy == "binary" {
+            let verdict = if summary.max() <= bound { catalog.get("bench_yes") }
+                          else { catalog.get("bench_no") };
+            println!("{}", catalog.format("bench_within", &[
+                ("verdict", &verdict), ("max", &summary.max()), ("bound", &bound),
+            ]));
+            println!();
         }
     }
 }
+
+/// Hosts a game for several players until killed.
+fn serve(options: &Options, port: u16) {
+    let lobby = Lobby::new(options.rules, open_source(&options.source))
+        .unwrap_or_else(|err| fail(&err));
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap_or_else(|err| fail(&err));
+
+    println!("Serving on {}. Press Ctrl-C to stop.",
+             listener.local_addr().unwrap_or_else(|err| fail(&err)));
+    if let Err(err) = server::serve(listener, lobby) {
+        fail(&err);
+    }
+}
+
+/// Serves games over HTTP until killed.
+fn serve_http(options: &Options, port: u16, expire_secs: u64) {
+    let sessions = Sessions::new(options.rules, open_source(&options.source),
+                                 Duration::from_secs(expire_secs));
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap_or_else(|err| fail(&err));
+
+    println!("Serving games on http://{}/games. Press Ctrl-C to stop.",
+             listener.local_addr().unwrap_or_else(|err| fail(&err)));
+    if let Err(err) = http::serve(listener, sessions) {
+        fail(&err);
+    }
+}
+
+/// Plays on a server started with `serve`.
+fn join(options: &Options, host: &str, port: u16) {
+    let stream = TcpStream::connect((host, port)).unwrap_or_else(|err| fail(&err));
+    let stdin = io::stdin();
+
+    let catalog = Catalog::choose(options.lang.as_deref());
+    if let Err(err) = client::run(stream, &player_name(options), catalog, stdin.lock(), io::stdout()) {
+        println!("{}", err);
+        process::exit(EXIT_IO);
+    }
+}
@@ -0,0 +1,569 @@
+//! Command line options for the guessing game binary.
+//!
+//! We only need a handful of flags, so they are parsed by hand instead of
+//! pulling in an argument parsing crate.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use difficulty::Difficulty;
+use game::Rules;
+use http::{DEFAULT_EXPIRE_SECS, DEFAULT_HTTP_PORT};
+use i18n::{Catalog, LANGUAGES};
+use scores::Filter;
+use secret::SourceSpec;
+use server::DEFAULT_PORT;
+use timeattack::{DEFAULT_SECONDS, MAX_SECONDS};
+
+/// Printed for `--help` and after a bad command line.
+pub const USAGE: &str = "\
+Usage: guessing_game [COMMAND] [OPTIONS]
+
+Commands:
+    play                play against the computer (the default)
+    bench               let the solver strategies play and compare them
+    reverse             think of a number and let the computer guess it
+    scores              show the best games on the leaderboard
+    serve               host a game for several players over TCP
+    client              join a game hosted with serve
+    http                serve games over HTTP/JSON on localhost, for
+                        playing from a web page
+    mastermind          crack a code of four coloured pegs
+    words               guess a five letter word
+    time-attack         find the secret before the clock runs out
+    replay FILE         play back a game saved with --record, checking
+                        that the game still answers the same way
+
+Options:
+    --min N             smallest possible secret (default 1)
+    --max N             largest possible secret (default 100)
+    --max-attempts N    number of guesses allowed (default unlimited;
+                        10 for mastermind, 6 for words)
+    --seed N            pick the secret from a seeded generator, so the
+                        same seed always gives the same game
+    --secret N          play with N as the secret (mastermind: the
+                        code's number, words: the word's place in the list)
+    --secrets-file F    take secrets from file F, one number per line
+    --self-test         play: check that the secret source is uniform
+                        over the range instead of playing
+    --games N           bench: games per strategy (default 1000)
+    --name NAME         your name on the leaderboard (default $USER)
+    --scores-file F     where the leaderboard is kept (default
+                        $GUESSING_GAME_SCORES or ~/.guessing_game_scores.json)
+    --top N             scores: how many games to show (default 10)
+    --difficulty NAME   easy, normal, hard or nightmare: sets the range,
+                        attempts and hints. With scores: only show games
+                        played at this difficulty
+    --port N            serve, client: TCP port (default 7878);
+                        http: (default 8080)
+    --expire N          http: forget games idle for N seconds
+                        (default 600)
+    --host H            client: where the server runs (default 127.0.0.1)
+    --words-file F      words: take the words from file F, one per line
+    --record F          play: save every prompt, line and answer to F
+    --speed N           replay: play back N times faster, 0 for no pauses
+                        (default 1)
+    --seconds N         time-attack: seconds on the clock, at most 3600
+                        (default 30)
+    --tui               play: full screen instead of a line prompt, Esc
+                        gives up
+    --adaptive          play: pick the range and attempts from your last
+                        games, aiming for a win rate of 70%
+    --lang CODE         play, time-attack, reverse, bench, mastermind, words,
+                        client: the language to talk in, one of en, de, fr
+                        or es (default from $LANG, else en). replay talks
+                        in the language of the recording
+    -h, --help          print this message
+
+Exit status:
+    0    you won
+    1    you ran out of attempts, you were caught cheating in reverse,
+         --self-test found the source unfair, or replay got a different
+         answer than the recording
+    2    bad command line
+    3    input closed before the game was over
+    4    input could not be read";
+
+/// What the binary should do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Command {
+    /// Play a game on the terminal.
+    #[default]
+    Play,
+    /// Let every solver strategy play `games` games and print statistics.
+    Bench { games: u32 },
+    /// The player thinks of a number, the computer guesses.
+    Reverse,
+    /// Show the `top` best games that match `filter`. The range filter is
+    /// only set if `--min` or `--max` was given.
+    Scores { top: usize, filter: Filter },
+    /// Host a game for several players on `port` of localhost.
+    Serve { port: u16 },
+    /// Join the game served on `host` and `port`.
+    Client { host: String, port: u16 },
+    /// Serve games over HTTP on `port` of localhost, forgetting those
+    /// idle for `expire_secs`.
+    Http { port: u16, expire_secs: u64 },
+    /// Play the Mastermind puzzle.
+    Mastermind,
+    /// Play the word puzzle.
+    Words,
+    /// Play against a clock of `seconds`.
+    TimeAttack { seconds: u64 },
+    /// Play back the recording in `file`, `speed` times faster than it
+    /// was played, or without pauses if `speed` is 0.
+    Replay { file: PathBuf, speed: u32 },
+}
+
+/// Games per strategy when `--games` isn't given.
+pub const DEFAULT_BENCH_GAMES: u32 = 1000;
+
+/// Leaderboard entries shown when `--top` isn't given.
+pub const DEFAULT_TOP: usize = 10;
+
+/// Everything the command line can ask for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    /// The subcommand.
+    pub command: Command,
+    /// The range and attempt limit to play with.
+    pub rules: Rules,
+    /// Where the secret comes from.
+    pub source: SourceSpec,
+    /// The preset given with `--difficulty`, already applied to `rules`.
+    pub difficulty: Option<Difficulty>,
+    /// Name to record scores under. `None` uses the login name.
+    pub player: Option<String>,
+    /// Where the leaderboard is kept. `None` uses `scores::default_path`.
+    pub scores_file: Option<PathBuf>,
+    /// The word list for `words`. `None` uses the built-in list.
+    pub words_file: Option<PathBuf>,
+    /// `--self-test` was given.
+    pub self_test: bool,
+    /// `--tui` was given.
+    pub tui: bool,
+    /// `--adaptive` was given: the rules come from the player's history.
+    pub adaptive: bool,
+    /// Where `--record` saves the game.
+    pub record: Option<PathBuf>,
+    /// The language given with `--lang`, known to have a catalog. `None`
+    /// goes by the locale.
+    pub lang: Option<String>,
+    /// `--help` was given.
+    pub help: bool,
+}
+
+/// Parses the command line arguments, without the program name.
+///
+/// Flags take their value either as the next argument (`--max 50`) or
+/// after an equals sign (`--max=50`).
+///
+/// # Failures
+/// Returns a message for unknown flags, missing or malformed values, and
+/// rules that don't describe a playable game.
+pub fn parse<I>(args: I) -> Result<Options, String>
+    where I: IntoIterator<Item = String>
+{
+    let mut options = Options::default();
+    let mut args = args.into_iter().peekable();
+    let mut games = None;
+    let mut top = None;
+    let mut attempts_given = false;
+    let mut range_given = false;
+    let mut port = None;
+    let mut host = None;
+    let mut speed = None;
+    let mut seconds = None;
+    let mut expire = None;
+
+    // An optional subcommand comes first.
+    let command = match args.peek() {
+        Some(arg) if !arg.starts_with('-') => args.next(),
+        _ => None,
+    };
+    // replay takes the file to play as well.
+    let replay_file = match (command.as_ref().map(|c| &c[..]), args.peek()) {
+        (Some("replay"), Some(arg)) if !arg.starts_with('-') => args.next(),
+        _ => None,
+    };
+
+    while let Some(arg) = args.next() {
+        // Split "--flag=value" into its two halves.
+        let (flag, inline_value) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => {
+                (arg[..i].to_string(), Some(arg[i + 1..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+
+        match &flag[..] {
+            "-h" | "--help" => options.help = true,
+            "--min" => {
+                options.rules.min = number(&flag, inline_value.or_else(|| args.next()))?;
+                range_given = true;
+            }
+            "--max" => {
+                options.rules.max = number(&flag, inline_value.or_else(|| args.next()))?;
+                range_given = true;
+            }
+            "--seed" | "--secret" | "--secrets-file" => {
+                if options.source != SourceSpec::Os {
+                    return Err("only one of --seed, --secret and --secrets-file \
+                                can be given".to_string());
+                }
+                let value = inline_value.or_else(|| args.next());
+                options.source = match &flag[..] {
+                    "--seed" => SourceSpec::Seeded(number(&flag, value)?),
+                    "--secret" => SourceSpec::Fixed(number(&flag, value)?),
+                    _ => SourceSpec::File(PathBuf::from(text(&flag, value)?)),
+                };
+            }
+            "--self-test" => options.self_test = true,
+            "--tui" => options.tui = true,
+            "--adaptive" => options.adaptive = true,
+            "--record" => {
+                let path = text(&flag, inline_value.or_else(|| args.next()))?;
+                options.record = Some(PathBuf::from(path));
+            }
+            "--lang" => {
+                let lang = text(&flag, inline_value.or_else(|| args.next()))?;
+                if Catalog::new(&lang).is_none() {
+                    let codes: Vec<&str> = LANGUAGES.iter().map(|&(code, _)| code).collect();
+                    return Err(format!("unknown language '{}', choose from {}",
+                                       lang, codes.join(", ")));
+                }
+                options.lang = Some(lang);
+            }
+            "--seconds" => seconds = Some(number(&flag, inline_value.or_else(|| args.next()))?),
+            "--expire" => expire = Some(number(&flag, inline_value.or_else(|| args.next()))?),
+            "--speed" => speed = Some(number(&flag, inline_value.or_else(|| args.next()))?),
+            "--games" => games = Some(number(&flag, inline_value.or_else(|| args.next()))?),
+            "--name" => options.player = Some(text(&flag, inline_value.or_else(|| args.next()))?),
+            "--scores-file" => {
+                let path = text(&flag, inline_value.or_else(|| args.next()))?;
+                options.scores_file = Some(PathBuf::from(path));
+            }
+            "--words-file" => {
+                let path = text(&flag, inline_value.or_else(|| args.next()))?;
+                options.words_file = Some(PathBuf::from(path));
+            }
+            "--port" => port = Some(number(&flag, inline_value.or_else(|| args.next()))?),
+            "--host" => host = Some(text(&flag, inline_value.or_else(|| args.next()))?),
+            "--top" => top = Some(number(&flag, inline_value.or_else(|| args.next()))?),
+            "--difficulty" => {
+                let name = text(&flag, inline_value.or_else(|| args.next()))?;
+                options.difficulty = Some(name.parse()?);
+            }
+            "--max-attempts" => {
+                attempts_given = true;
+                let n = number(&flag, inline_value.or_else(|| args.next()))?;
+                options.rules.max_attempts = Some(n);
+            }
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    options.command = match command.as_ref().map(|c| &c[..]) {
+        None | Some("play") => Command::Play,
+        Some("bench") => Command::Bench { games: games.unwrap_or(DEFAULT_BENCH_GAMES) },
+        Some("reverse") => Command::Reverse,
+        Some("scores") => {
+            let range = (options.rules.min, options.rules.max);
+            Command::Scores {
+                top: top.unwrap_or(DEFAULT_TOP),
+                filter: Filter {
+                    range: if range_given { Some(range) } else { None },
+                    difficulty: options.difficulty.map(|d| d.name().to_string()),
+                },
+            }
+        }
+        Some("serve") => Command::Serve { port: port.unwrap_or(DEFAULT_PORT) },
+        Some("client") => Command::Client {
+            host: host.take().unwrap_or_else(|| "127.0.0.1".to_string()),
+            port: port.unwrap_or(DEFAULT_PORT),
+        },
+        Some("http") => match expire.unwrap_or(DEFAULT_EXPIRE_SECS) {
+            0 => return Err("--expire must be at least 1".to_string()),
+            expire_secs => Command::Http { port: port.unwrap_or(DEFAULT_HTTP_PORT), expire_secs },
+        },
+        Some("mastermind") => Command::Mastermind,
+        Some("words") => Command::Words,
+        Some("time-attack") => match seconds.unwrap_or(DEFAULT_SECONDS) {
+            0 => return Err("--seconds must be at least 1".to_string()),
+            seconds if seconds > MAX_SECONDS => {
+                return Err(format!("--seconds can be at most {}", MAX_SECONDS))
+            }
+            seconds => Command::TimeAttack { seconds },
+        },
+        Some("replay") => match replay_file {
+            Some(file) => Command::Replay { file: PathBuf::from(file), speed: speed.unwrap_or(1) },
+            None => return Err("replay needs the file to play".to_string()),
+        },
+        Some(other) => return Err(format!("unknown command '{}'", other)),
+    };
+    if games.is_some() && !matches!(options.command, Command::Bench { .. }) {
+        return Err("--games only applies to bench".to_string());
+    }
+    if top.is_some() && !matches!(options.command, Command::Scores { .. }) {
+        return Err("--top only applies to scores".to_string());
+    }
+    if options.self_test && options.command != Command::Play {
+        return Err("--self-test only applies to play".to_string());
+    }
+    if options.tui && options.command != Command::Play {
+        return Err("--tui only applies to play".to_string());
+    }
+    if options.adaptive {
+        if options.command != Command::Play {
+            return Err("--adaptive only applies to play".to_string());
+        }
+        if range_given || attempts_given || options.difficulty.is_some() {
+            return Err("--adaptive picks the range and attempts, leave out --min, --max, \
+                        --max-attempts and --difficulty".to_string());
+        }
+    }
+    if options.record.is_some() && (options.command != Command::Play || options.tui) {
+        return Err("--record only applies to play, without --tui".to_string());
+    }
+    if options.lang.is_some()
+        && matches!(options.command, Command::Replay { .. } | Command::Scores { .. }
+                                     | Command::Serve { .. } | Command::Http { .. })
+    {
+        return Err("--lang only applies to play, time-attack, reverse, bench, mastermind, \
+                    words and client".to_string());
+    }
+    if seconds.is_some() && !matches!(options.command, Command::TimeAttack { .. }) {
+        return Err("--seconds only applies to time-attack".to_string());
+    }
+    if speed.is_some() && !matches!(options.command, Command::Replay { .. }) {
+        return Err("--speed only applies to replay".to_string());
+    }
+    if options.words_file.is_some() && options.command != Command::Words {
+        return Err("--words-file only applies to words".to_string());
+    }
+    if matches!(options.command, Command::Mastermind | Command::Words)
+        && (range_given || options.difficulty.is_some())
+    {
+        return Err("mastermind and words have no range, leave out --min, --max \
+                    and --difficulty".to_string());
+    }
+    if let Some(difficulty) = options.difficulty {
+        if !matches!(options.command, Command::Scores { .. }) {
+            if range_given || attempts_given {
+                return Err("--difficulty already sets the range and attempts, \
+                            leave out --min, --max and --max-attempts".to_string());
+            }
+            options.rules = difficulty.rules();
+        }
+    }
+    if expire.is_some() && !matches!(options.command, Command::Http { .. }) {
+        return Err("--expire only applies to http".to_string());
+    }
+    if port.is_some()
+        && !matches!(options.command, Command::Serve { .. } | Command::Client { .. } | Command::Http { .. })
+    {
+        return Err("--port only applies to serve, client and http".to_string());
+    }
+    if host.is_some() {
+        return Err("--host only applies to client".to_string());
+    }
+
+    options.rules.validate()?;
+    Ok(options)
+}
+
+/// The value of a flag that takes any text.
+fn text(flag: &str, value: Option<String>) -> Result<String, String> {
+    match value {
+        Some(ref value) if value.is_empty() => Err(format!("{} needs a value", flag)),
+        Some(value) => Ok(value),
+        None => Err(format!("{} needs a value", flag)),
+    }
+}
+
+/// Parses the value of a numeric flag.
+fn number<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = match value {
+        Some(value) => value,
+        None => return Err(format!("{} needs a value", flag)),
+    };
+    value.trim().parse()
+        .map_err(|_| format!("{} expects a whole number, got '{}'", flag, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(Ok(Options::default()), parse(args("")));
+    }
+
+    #[test]
+    fn range_and_attempts() {
+        let options = parse(args("--min 10 --max=20 --max-attempts 3")).unwrap();
+        assert_eq!(Rules { min: 10, max: 20, max_attempts: Some(3) }, options.rules);
+    }
+
+    #[test]
+    fn sources() {
+        assert_eq!(SourceSpec::Seeded(1234567890123),
+                   parse(args("--seed 1234567890123")).unwrap().source);
+        assert_eq!(SourceSpec::Fixed(9), parse(args("--secret=9")).unwrap().source);
+        assert_eq!(SourceSpec::File(PathBuf::from("s.txt")),
+                   parse(args("--secrets-file s.txt")).unwrap().source);
+        assert!(parse(args("--seed -1")).is_err());
+        assert!(parse(args("--seed 1 --secret 2")).is_err());
+
+        assert!(parse(args("--self-test --seed 7")).unwrap().self_test);
+        assert_eq!(Err("--self-test only applies to play".to_string()),
+                   parse(args("bench --self-test")));
+        assert!(parse(args("serve --self-test")).is_err());
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(Command::Play, parse(args("play --max 5")).unwrap().command);
+        assert_eq!(Command::Bench { games: DEFAULT_BENCH_GAMES },
+                   parse(args("bench")).unwrap().command);
+        assert_eq!(Command::Bench { games: 20 },
+                   parse(args("bench --games 20 --seed 1")).unwrap().command);
+        assert_eq!(Command::Reverse, parse(args("reverse --min 0")).unwrap().command);
+        assert!(parse(args("--games 20")).is_err());
+        assert!(parse(args("reverse --games 20")).is_err());
+        assert!(parse(args("dance")).is_err());
+        assert!(parse(args("--max 5 bench")).is_err());
+    }
+
+    #[test]
+    fn scores() {
+        let options = parse(args("scores --name ada --scores-file s.json")).unwrap();
+        assert_eq!(Command::Scores { top: DEFAULT_TOP, filter: Filter::default() },
+                   options.command);
+        assert_eq!(Some("ada".to_string()), options.player);
+        assert_eq!(Some(PathBuf::from("s.json")), options.scores_file);
+
+        let options = parse(args("scores --max 50 --top 3 --difficulty hard")).unwrap();
+        assert_eq!(Command::Scores {
+            top: 3,
+            filter: Filter { range: Some((1, 50)), difficulty: Some("hard".to_string()) },
+        }, options.command);
+
+        assert!(parse(args("--top 3")).is_err());
+        assert!(parse(args("--name")).is_err());
+    }
+
+    #[test]
+    fn difficulty() {
+        let options = parse(args("--difficulty hard")).unwrap();
+        assert_eq!(Some(Difficulty::Hard), options.difficulty);
+        assert_eq!(Difficulty::Hard.rules(), options.rules);
+        assert!(parse(args("--difficulty hard --max 10")).is_err());
+        assert!(parse(args("--difficulty silly")).is_err());
+    }
+
+    #[test]
+    fn network() {
+        assert_eq!(Command::Serve { port: DEFAULT_PORT }, parse(args("serve")).unwrap().command);
+        assert_eq!(Command::Client { host: "10.0.0.2".to_string(), port: 9000 },
+                   parse(args("client --host 10.0.0.2 --port 9000")).unwrap().command);
+        assert!(parse(args("--port 9000")).is_err());
+        assert!(parse(args("serve --host 10.0.0.2")).is_err());
+        assert!(parse(args("serve --port 70000")).is_err());
+
+        assert_eq!(Command::Http { port: DEFAULT_HTTP_PORT, expire_secs: DEFAULT_EXPIRE_SECS },
+                   parse(args("http")).unwrap().command);
+        assert_eq!(Command::Http { port: 9000, expire_secs: 30 },
+                   parse(args("http --port 9000 --expire 30")).unwrap().command);
+        assert!(parse(args("http --expire 0")).is_err());
+        assert!(parse(args("serve --expire 30")).is_err());
+    }
+
+    #[test]
+    fn puzzles() {
+        assert_eq!(Command::Mastermind,
+                   parse(args("mastermind --max-attempts 5")).unwrap().command);
+        let options = parse(args("words --words-file w.txt --seed 3")).unwrap();
+        assert_eq!(Command::Words, options.command);
+        assert_eq!(Some(PathBuf::from("w.txt")), options.words_file);
+        assert!(parse(args("--words-file w.txt")).is_err());
+        assert!(parse(args("mastermind --max 5")).is_err());
+        assert!(parse(args("words --difficulty easy")).is_err());
+    }
+
+    #[test]
+    fn tui() {
+        assert!(parse(args("--tui --difficulty easy")).unwrap().tui);
+        assert!(!parse(args("play")).unwrap().tui);
+        assert!(parse(args("words --tui")).is_err());
+    }
+
+    #[test]
+    fn recording() {
+        let options = parse(args("--record game.jsonl --seed 1")).unwrap();
+        assert_eq!(Some(PathBuf::from("game.jsonl")), options.record);
+        assert_eq!(Command::Replay { file: PathBuf::from("game.jsonl"), speed: 1 },
+                   parse(args("replay game.jsonl")).unwrap().command);
+        assert_eq!(Command::Replay { file: PathBuf::from("game.jsonl"), speed: 0 },
+                   parse(args("replay game.jsonl --speed=0")).unwrap().command);
+        assert!(parse(args("replay")).is_err());
+        assert!(parse(args("replay --speed 2")).is_err());
+        assert!(parse(args("--speed 2")).is_err());
+        assert!(parse(args("--record game.jsonl --tui")).is_err());
+        assert!(parse(args("words --record game.jsonl")).is_err());
+    }
+
+    #[test]
+    fn time_attack() {
+        assert_eq!(Command::TimeAttack { seconds: DEFAULT_SECONDS },
+                   parse(args("time-attack --difficulty hard")).unwrap().command);
+        assert_eq!(Command::TimeAttack { seconds: 5 },
+                   parse(args("time-attack --seconds 5")).unwrap().command);
+        assert!(parse(args("time-attack --seconds 0")).is_err());
+        assert_eq!(Err("--seconds can be at most 3600".to_string()),
+                   parse(args("time-attack --seconds 18446744073709551615")));
+        assert!(parse(args("time-attack --seconds 3601")).is_err());
+        assert!(parse(args("time-attack --seconds 3600")).is_ok());
+        assert!(parse(args("--seconds 5")).is_err());
+    }
+
+    #[test]
+    fn adaptive() {
+        assert!(parse(args("--adaptive")).unwrap().adaptive);
+        assert!(parse(args("--adaptive --tui")).unwrap().adaptive);
+        assert!(parse(args("--adaptive --max 50")).is_err());
+        assert!(parse(args("--adaptive --difficulty easy")).is_err());
+        assert!(parse(args("bench --adaptive")).is_err());
+    }
+
+    #[test]
+    fn languages() {
+        assert_eq!(None, parse(args("")).unwrap().lang);
+        assert_eq!(Some("de".to_string()), parse(args("--lang de")).unwrap().lang);
+        assert_eq!(Some("fr".to_string()), parse(args("time-attack --lang=fr")).unwrap().lang);
+        assert_eq!(Err("unknown language 'xx', choose from en, de, fr, es".to_string()),
+                   parse(args("--lang xx")));
+        assert_eq!(Some("de".to_string()), parse(args("words --lang de")).unwrap().lang);
+        assert!(parse(args("serve --lang de")).is_err());
+    }
+
+    #[test]
+    fn help() {
+        assert!(parse(args("--help")).unwrap().help);
+        assert!(parse(args("-h")).unwrap().help);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse(args("--max")).is_err());
+        assert!(parse(args("--max ten")).is_err());
+        assert!(parse(args("--min 50 --max 10")).is_err());
+        assert!(parse(args("--max-attempts 0")).is_err());
+        assert!(parse(args("--colour")).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+//! Mastermind: guess a code of coloured pegs.
+//!
+//! After each guess the player hears how many pegs are the right colour in
+//! the right place (black pegs), and how many more are the right colour in
+//! the wrong place (white pegs).
+
+use std::error::Error;
+use std::fmt;
+
+use game::Rules;
+use i18n::Catalog;
+use secret::{SecretError, SecretSource};
+use super::Puzzle;
+
+/// Pegs in a code.
+pub const PEGS: usize = 4;
+
+/// The colours a peg can have, by their letter.
+pub const COLOURS: [char; 6] = ['R', 'G', 'B', 'Y', 'O', 'P'];
+
+/// Guesses allowed when no limit is given.
+pub const DEFAULT_ATTEMPTS: u32 = 10;
+
+/// A code: each peg is an index into `COLOURS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Code(pub [u8; PEGS]);
+
+/// Why a line is not a code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeError {
+    /// It doesn't have `PEGS` letters.
+    Length,
+    /// The letter, upper case, is not one of `COLOURS`.
+    NotAColour(char),
+}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::english().code_error(self))
+    }
+}
+
+impl Error for CodeError {}
+
+impl Code {
+    /// Parses a code written as colour letters, such as "RGBY" or "r g b y".
+    pub fn parse(text: &str) -> Result<Code, CodeError> {
+        let letters: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+        if letters.len() != PEGS {
+            return Err(CodeError::Length);
+        }
+
+        let mut pegs = [0; PEGS];
+        for (peg, &letter) in pegs.iter_mut().zip(&letters) {
+            let letter = letter.to_ascii_uppercase();
+            match COLOURS.iter().position(|&c| c == letter) {
+                Some(colour) => *peg = colour as u8,
+                None => return Err(CodeError::NotAColour(letter)),
+            }
+        }
+        Ok(Code(pegs))
+    }
+
+    /// The code numbered `n` when codes are counted in base `COLOURS.len()`.
+    pub fn from_number(mut n: u32) -> Code {
+        let base = COLOURS.len() as u32;
+        let mut pegs = [0; PEGS];
+        for peg in pegs.iter_mut().rev() {
+            *peg = (n % base) as u8;
+            n /= base;
+        }
+        Code(pegs)
+    }
+
+    /// How many different codes there are.
+    pub fn count() -> u32 {
+        (COLOURS.len() as u32).pow(PEGS as u32)
+    }
+
+    /// Scores `guess` against this code.
+    pub fn score(&self, guess: &Code) -> Pegs {
+        let mut black = 0;
+        let mut secret_left = [0; COLOURS.len()];
+        let mut guess_left = [0; COLOURS.len()];
+
+        for (&s, &g) in self.0.iter().zip(&guess.0) {
+            if s == g {
+                black += 1;
+            } else {
+                secret_left[s as usize] += 1;
+                guess_left[g as usize] += 1;
+            }
+        }
+        // Each colour left over can be matched as often as it appears on
+        // both sides.
+        let white = secret_left.iter().zip(&guess_left).map(|(&s, &g)| s.min(g)).sum();
+
+        Pegs { black, white }
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &peg in &self.0 {
+            write!(f, "{}", COLOURS[peg as usize])?;
+        }
+        Ok(())
+    }
+}
+
+/// The colour letters, such as "R G B Y O P".
+pub fn colour_letters() -> String {
+    let names: Vec<String> = COLOURS.iter().map(|c| c.to_string()).collect();
+    names.join(" ")
+}
+
+/// The feedback for one guess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pegs {
+    /// Right colour, right place.
+    pub black: u32,
+    /// Right colour, wrong place.
+    pub white: u32,
+}
+
+/// A game of Mastermind.
+pub struct Mastermind {
+    secret: Code,
+    max_attempts: u32,
+    attempts: u32,
+    catalog: Catalog,
+}
+
+impl Mastermind {
+    /// # Panics
+    /// Panics if `max_attempts` is 0.
+    pub fn new(secret: Code, max_attempts: u32) -> Mastermind {
+        assert!(max_attempts > 0, "a game needs at least one attempt");
+        Mastermind { secret, max_attempts, attempts: 0, catalog: Catalog::english().clone() }
+    }
+
+    /// The same game, talking in the language of `catalog`.
+    pub fn with_catalog(self, catalog: Catalog) -> Mastermind {
+        Mastermind { catalog, ..self }
+    }
+
+    /// Starts a game with a code drawn from `source`.
+    pub fn draw<S: SecretSource + ?Sized>(source: &mut S, max_attempts: u32)
+        -> Result<Mastermind, SecretError>
+    {
+        let rules = Rules { min: 0, max: Code::count() - 1, max_attempts: None };
+        let n = source.next_secret(&rules)?;
+        Ok(Mastermind::new(Code::from_number(n), max_attempts))
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
+
+impl Puzzle for Mastermind {
+    type Guess = Code;
+    type Feedback = Pegs;
+
+    fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    fn intro(&self) -> String {
+        let colours = self.catalog.format("mm_colours", &[("colours", &colour_letters())]);
+        format!("{}\n{}\n{}", self.catalog.get("mm_title"),
+                self.catalog.format("mm_pegs", &[("pegs", &PEGS), ("colours", &colours)]),
+                self.catalog.get("mm_rules"))
+    }
+
+    fn parse_guess(&self, line: &str) -> Result<Code, String> {
+        Code::parse(line).map_err(|err| self.catalog.code_error(&err))
+    }
+
+    fn guess(&mut self, guess: &Code) -> Pegs {
+        self.attempts += 1;
+        self.secret.score(guess)
+    }
+
+    fn is_win(&self, feedback: &Pegs) -> bool {
+        feedback.black == PEGS as u32
+    }
+
+    fn show(&self, guess: &Code, feedback: &Pegs) -> String {
+        let verdict = if self.is_win(feedback) {
+            self.catalog.get("correct").to_string()
+        } else {
+            self.catalog.format("mm_result", &[("black", &feedback.black), ("white", &feedback.white)])
+        };
+        format!("{}\n{}", self.catalog.format("guessed", &[("guess", guess)]), verdict)
+    }
+
+    fn attempts_left(&self) -> Option<u32> {
+        Some(self.max_attempts - self.attempts)
+    }
+
+    fn reveal(&self) -> String {
+        self.catalog.format("mm_reveal", &[("code", &self.secret)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::Status;
+    use puzzle;
+    use secret::FixedSource;
+    use std::io::Cursor;
+
+    fn code(text: &str) -> Code {
+        Code::parse(text).unwrap()
+    }
+
+    fn score(secret: &str, guess: &str) -> (u32, u32) {
+        let pegs = code(secret).score(&code(guess));
+        (pegs.black, pegs.white)
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Code([0, 1, 2, 3]), code("RGBY"));
+        assert_eq!(Code([5, 4, 0, 0]), code(" p o r r "));
+        assert_eq!(Err(CodeError::Length), Code::parse("RGB"));
+        assert_eq!(Err(CodeError::Length), Code::parse("RGBYO"));
+        assert_eq!(Err(CodeError::NotAColour('X')), Code::parse("RGBX"));
+        assert_eq!("'X' is not a colour. Colours are R G B Y O P.",
+                   CodeError::NotAColour('X').to_string());
+    }
+
+    #[test]
+    fn numbering() {
+        assert_eq!(1296, Code::count());
+        assert_eq!(code("RRRR"), Code::from_number(0));
+        assert_eq!(code("RRRG"), Code::from_number(1));
+        assert_eq!(code("PPPP"), Code::from_number(Code::count() - 1));
+        assert_eq!("RGBY", code("rgby").to_string());
+    }
+
+    #[test]
+    fn pegs() {
+        assert_eq!((4, 0), score("RGBY", "RGBY"));
+        assert_eq!((0, 4), score("RGBY", "YBGR"));
+        assert_eq!((0, 0), score("RGBY", "OOPP"));
+        assert_eq!((2, 2), score("RGBY", "RGYB"));
+        // Repeated colours only count as often as they are in the code.
+        assert_eq!((1, 0), score("RGBY", "RRRR"));
+        assert_eq!((1, 1), score("RRGG", "RBBR"));
+        assert_eq!((0, 2), score("RRGG", "GBBR"));
+    }
+
+    #[test]
+    fn draw() {
+        let mut source = FixedSource(1);
+        let game = Mastermind::draw(&mut source, 3).unwrap();
+        assert_eq!("The code was RRRG.", game.reveal());
+        assert!(Mastermind::draw(&mut FixedSource(1296), 3).is_err());
+    }
+
+    #[test]
+    fn plays_through_the_driver() {
+        let mut game = Mastermind::new(code("RGBY"), 3);
+        let mut output = Vec::new();
+        let status = puzzle::play(&mut game, &mut Cursor::new("RGB\nYBGR\nrgby\n"), &mut output);
+
+        assert_eq!(Status::Won, status.unwrap());
+        assert_eq!(2, game.attempts());
+        assert!(String::from_utf8(output).unwrap().ends_with("\
+Please input your guess.
+A code has 4 pegs, like RGBY.
+Please input your guess.
+You guessed: YBGR
+Black: 0, white: 4
+2 attempt(s) left.
+Please input your guess.
+You guessed: RGBY
+You win!
+"));
+    }
+
+    #[test]
+    fn talks_in_other_languages() {
+        let mut game = Mastermind::new(code("RGBY"), 1).with_catalog(Catalog::new("fr").unwrap());
+        let mut output = Vec::new();
+        let status = puzzle::play(&mut game, &mut Cursor::new("RGBX\nRRRR\n"), &mut output);
+
+        assert_eq!(Status::Lost, status.unwrap());
+        assert_eq!("\
+Percez le code !
+Il a 4 fiches. Les couleurs sont R G B Y O P.
+Fiche noire : bonne couleur, bonne place ; fiche blanche : bonne couleur, mauvaise place.
+Entrez votre proposition.
+'X' n'est pas une couleur. Les couleurs sont R G B Y O P.
+Entrez votre proposition.
+Vous avez proposé : RRRR
+Noires : 1, blanches : 0
+Perdu ! Le code était RGBY.
+", String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,248 @@
+//! Games built on the same guess → feedback cycle as the number game.
+//!
+//! A `Puzzle` decides what a guess is, what the feedback looks like and
+//! when the player has won. `play` is the one loop that runs any of them
+//! on a line based terminal: prompt, read, parse, answer, repeat. The
+//! lines come from a `Lines`, which is any reader, or the clock and
+//! reader threads of a time attack.
+
+use std::io::{BufRead, Write};
+
+use game::Status;
+use i18n::Catalog;
+use input::{self, InputError};
+
+pub mod mastermind;
+pub mod number;
+pub mod words;
+
+pub use self::mastermind::Mastermind;
+pub use self::number::NumberPuzzle;
+pub use self::words::WordPuzzle;
+
+/// A game of guesses and feedback.
+pub trait Puzzle {
+    /// One attempt at the solution.
+    type Guess;
+    /// What the puzzle says about a guess.
+    type Feedback;
+
+    /// The lines shown before the first prompt.
+    fn intro(&self) -> String;
+
+    /// The messages the shared loop uses. English unless the puzzle
+    /// was made for another language.
+    fn catalog(&self) -> &Catalog {
+        Catalog::english()
+    }
+
+    /// The question asked before each line is read.
+    fn prompt(&self) -> String {
+        self.catalog().get("prompt").to_string()
+    }
+
+    /// Turns a line typed by the player into a guess.
+    ///
+    /// # Failures
+    /// Returns the message to show the player.
+    fn parse_guess(&self, line: &str) -> Result<Self::Guess, String>;
+
+    /// Handles a line that is a command rather than a guess, such as a
+    /// request for a hint. Returns what to show, or `None` if the line
+    /// isn't a command. Most puzzles have no commands.
+    fn command(&mut self, _line: &str) -> Option<String> {
+        None
+    }
+
+    /// Checks a guess and counts it as an attempt, if it counts.
+    fn guess(&mut self, guess: &Self::Guess) -> Self::Feedback;
+
+    /// The win condition: whether `feedback` means the puzzle is solved.
+    fn is_win(&self, feedback: &Self::Feedback) -> bool;
+
+    /// The lines shown for a guess and its feedback.
+    fn show(&self, guess: &Self::Guess, feedback: &Self::Feedback) -> String;
+
+    /// Attempts left, or `None` if there is no limit.
+    fn attempts_left(&self) -> Option<u32>;
+
+    /// Tells a player who lost what the solution was.
+    fn reveal(&self) -> String;
+}
+
+/// What the player did while `Lines` waited for them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Next {
+    /// They typed a line.
+    Line(String),
+    /// They ran out of time.
+    TimeUp,
+}
+
+/// Where the player's lines come from.
+pub trait Lines {
+    /// Called once the intro is shown, to add to it.
+    fn started<W: Write>(&mut self, _catalog: &Catalog, _output: &mut W) -> Result<(), InputError> {
+        Ok(())
+    }
+
+    /// Waits for the next line. Anything to tell the player meanwhile,
+    /// such as the time left, goes to `output`.
+    fn next<W: Write>(&mut self, catalog: &Catalog, output: &mut W) -> Result<Next, InputError>;
+}
+
+impl<R: BufRead> Lines for R {
+    fn next<W: Write>(&mut self, _catalog: &Catalog, _output: &mut W) -> Result<Next, InputError> {
+        input::read_line(self).map(Next::Line)
+    }
+}
+
+/// Plays `puzzle` to the end, reading guesses from `input` and writing
+/// everything the player sees to `output`.
+///
+/// Returns `Status::Won` or `Status::Lost`.
+///
+/// # Failures
+/// Returns `InputError::Eof` if `input` closes before the end, and
+/// `InputError::Io` if reading or writing fails.
+pub fn play<P, L, W>(puzzle: &mut P, input: &mut L, output: &mut W) -> Result<Status, InputError>
+    where P: Puzzle + ?Sized, L: Lines, W: Write
+{
+    let catalog = puzzle.catalog().clone();
+    writeln!(output, "{}", puzzle.intro())?;
+    input.started(&catalog, output)?;
+
+    loop {
+        writeln!(output, "{}", puzzle.prompt())?;
+        let line = match input.next(&catalog, output)? {
+            Next::Line(line) => line,
+            Next::TimeUp => {
+                writeln!(output, "{}", catalog.format("time_up", &[("reveal", &puzzle.reveal())]))?;
+                return Ok(Status::Lost);
+            }
+        };
+
+        if let Some(text) = puzzle.command(&line) {
+            writeln!(output, "{}", text)?;
+            continue;
+        }
+
+        let guess = match puzzle.parse_guess(&line) {
+            Ok(guess) => guess,
+            // A bad line: tell the player why, and ask again.
+            Err(message) => {
+                writeln!(output, "{}", message)?;
+                continue;
+            }
+        };
+
+        let feedback = puzzle.guess(&guess);
+        writeln!(output, "{}", puzzle.show(&guess, &feedback))?;
+
+        if puzzle.is_win(&feedback) {
+            return Ok(Status::Won);
+        }
+        match puzzle.attempts_left() {
+            Some(0) => {
+                writeln!(output, "{}", catalog.format("lose", &[("reveal", &puzzle.reveal())]))?;
+                return Ok(Status::Lost);
+            }
+            Some(left) => writeln!(output, "{}", catalog.format("attempts_left", &[("left", &left)]))?,
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Guess a letter. Three tries.
+    struct Letter {
+        secret: char,
+        tries: u32,
+    }
+
+    impl Puzzle for Letter {
+        type Guess = char;
+        type Feedback = bool;
+
+        fn intro(&self) -> String {
+            "Guess the letter!".to_string()
+        }
+
+        fn parse_guess(&self, line: &str) -> Result<char, String> {
+            let line = line.trim();
+            match (line.chars().next(), line.chars().count()) {
+                (Some(c), 1) => Ok(c),
+                _ => Err("One letter please.".to_string()),
+            }
+        }
+
+        fn command(&mut self, line: &str) -> Option<String> {
+            if line.trim() == "?" { Some("No help here.".to_string()) } else { None }
+        }
+
+        fn guess(&mut self, guess: &char) -> bool {
+            self.tries += 1;
+            *guess == self.secret
+        }
+
+        fn is_win(&self, feedback: &bool) -> bool {
+            *feedback
+        }
+
+        fn show(&self, guess: &char, feedback: &bool) -> String {
+            format!("{}: {}", guess, if *feedback { "yes" } else { "no" })
+        }
+
+        fn attempts_left(&self) -> Option<u32> {
+            Some(3 - self.tries)
+        }
+
+        fn reveal(&self) -> String {
+            format!("It was {}.", self.secret)
+        }
+    }
+
+    fn run(input: &str) -> (Result<Status, InputError>, String) {
+        let mut puzzle = Letter { secret: 'q', tries: 0 };
+        let mut output = Vec::new();
+        let result = play(&mut puzzle, &mut Cursor::new(input), &mut output);
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn win() {
+        let (result, output) = run("?\nab\nx\nq\n");
+        assert_eq!(Status::Won, result.unwrap());
+        assert_eq!("\
+Guess the letter!
+Please input your guess.
+No help here.
+Please input your guess.
+One letter please.
+Please input your guess.
+x: no
+2 attempt(s) left.
+Please input your guess.
+q: yes
+", output);
+    }
+
+    #[test]
+    fn loss() {
+        let (result, output) = run("a\nb\nc\nq\n");
+        assert_eq!(Status::Lost, result.unwrap());
+        assert!(output.ends_with("c: no\nYou lose! It was q.\n"), "{}", output);
+    }
+
+    #[test]
+    fn eof() {
+        match run("a\n").0 {
+            Err(InputError::Eof) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
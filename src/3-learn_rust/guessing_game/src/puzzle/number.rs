@@ -0,0 +1,169 @@
+//! The original game: guess a number, hear whether it is too small or
+//! too big.
+
+use game::{Feedback, Game};
+use hints::{Hint, Hints};
+use i18n::Catalog;
+use input;
+use super::Puzzle;
+
+/// A `Game` with the hints that can be bought during it.
+pub struct NumberPuzzle {
+    game: Game,
+    hints: Hints,
+    catalog: Catalog,
+}
+
+impl NumberPuzzle {
+    pub fn new(game: Game, hints: Hints) -> NumberPuzzle {
+        NumberPuzzle { game, hints, catalog: Catalog::english().clone() }
+    }
+
+    /// The same puzzle, talking in the language of `catalog`.
+    pub fn with_catalog(self, catalog: Catalog) -> NumberPuzzle {
+        NumberPuzzle { catalog, ..self }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn hints(&self) -> &Hints {
+        &self.hints
+    }
+
+    /// The answer to "hint NAME", or to a bare "hint" if `name` is empty.
+    fn hint(&mut self, name: &str) -> String {
+        if name.is_empty() {
+            return self.catalog.menu(&self.hints);
+        }
+        match Hint::parse(name) {
+            Some(hint) => match self.hints.ask(hint, &self.game) {
+                Ok(clue) => {
+                    let clue = self.catalog.clue(&clue);
+                    self.catalog.format("hint_bought", &[("clue", &clue), ("cost", &hint.cost())])
+                }
+                Err(err) => self.catalog.hint_error(&err),
+            },
+            None => {
+                let menu = self.catalog.menu(&self.hints);
+                self.catalog.format("unknown_hint", &[("name", &name), ("menu", &menu)])
+            }
+        }
+    }
+}
+
+impl Puzzle for NumberPuzzle {
+    type Guess = u32;
+    type Feedback = Feedback;
+
+    fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    fn intro(&self) -> String {
+        let rules = self.game.rules();
+        let mut intro = format!("{}\n{}", self.catalog.get("title"),
+                                self.catalog.format("range", &[("min", &rules.min), ("max", &rules.max)]));
+        if !self.hints.allowed().is_empty() {
+            intro.push('\n');
+            intro.push_str(self.catalog.get("hint_intro"));
+        }
+        intro
+    }
+
+    fn parse_guess(&self, line: &str) -> Result<u32, String> {
+        input::parse_guess(line).map_err(|err| self.catalog.input_error(&err))
+    }
+
+    fn command(&mut self, line: &str) -> Option<String> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some(word) if word.eq_ignore_ascii_case("hint") => {
+                Some(self.hint(words.next().unwrap_or("")))
+            }
+            _ => None,
+        }
+    }
+
+    fn guess(&mut self, guess: &u32) -> Feedback {
+        self.game.guess(*guess)
+    }
+
+    fn is_win(&self, feedback: &Feedback) -> bool {
+        *feedback == Feedback::Correct
+    }
+
+    fn show(&self, guess: &u32, feedback: &Feedback) -> String {
+        let rules = self.game.rules();
+        let verdict = match *feedback {
+            Feedback::TooSmall => self.catalog.get("too_small").to_string(),
+            Feedback::TooBig => self.catalog.get("too_big").to_string(),
+            Feedback::Correct => self.catalog.get("correct").to_string(),
+            Feedback::OutOfRange => {
+                self.catalog.format("out_of_range", &[("min", &rules.min), ("max", &rules.max)])
+            }
+        };
+        format!("{}\n{}", self.catalog.format("guessed", &[("guess", guess)]), verdict)
+    }
+
+    fn attempts_left(&self) -> Option<u32> {
+        self.game.attempts_left()
+    }
+
+    fn reveal(&self) -> String {
+        self.catalog.format("reveal", &[("secret", &self.game.secret())])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::{Rules, Status};
+    use puzzle;
+    use std::io::Cursor;
+
+    fn puzzle(allowed: &[Hint]) -> NumberPuzzle {
+        let rules = Rules { min: 1, max: 10, max_attempts: Some(2) };
+        NumberPuzzle::new(Game::with_rules(7, rules), Hints::new(allowed))
+    }
+
+    #[test]
+    fn hints_are_commands() {
+        let mut number = puzzle(&[Hint::Parity]);
+        assert!(number.intro().ends_with("Type 'hint' to see the hints you can buy."));
+        assert_eq!(Some("Hints: hint parity (-10)".to_string()), number.command("hint"));
+        assert_eq!(Some("The number is odd. (-10 points)".to_string()),
+                   number.command("HINT parity"));
+        assert_eq!(None, number.command("7"));
+        assert_eq!(10, number.hints().cost());
+
+        assert!(!puzzle(&[]).intro().contains("hint"));
+    }
+
+    #[test]
+    fn plays_through_the_driver() {
+        let mut number = puzzle(&[]);
+        let mut output = Vec::new();
+        let status = puzzle::play(&mut number, &mut Cursor::new("11\n3\n9\n"), &mut output);
+
+        assert_eq!(Status::Lost, status.unwrap());
+        assert_eq!(2, number.game().attempts());
+        assert_eq!("\
+Guess the number!
+It is between 1 and 10.
+Please input your guess.
+You guessed: 11
+Out of range! Guess between 1 and 10.
+2 attempt(s) left.
+Please input your guess.
+You guessed: 3
+Too small!
+1 attempt(s) left.
+Please input your guess.
+You guessed: 9
+Too big!
+You lose! The secret number was 7.
+", String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,365 @@
+//! A word game in the style of Wordle: guess a five letter word.
+//!
+//! Each letter of a guess is marked `=` if it is in the right place, `~`
+//! if the word has it somewhere else and `.` if it doesn't.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use game::Rules;
+use i18n::Catalog;
+use secret::{SecretError, SecretSource};
+use super::Puzzle;
+
+/// Letters in a word.
+pub const LETTERS: usize = 5;
+
+/// Guesses allowed when no limit is given.
+pub const DEFAULT_ATTEMPTS: u32 = 6;
+
+/// The list used when no `--words-file` is given.
+const BUILTIN: &str = include_str!("words.txt");
+
+/// Why a word list could not be loaded.
+#[derive(Debug)]
+pub enum WordsError {
+    /// The file could not be opened or read.
+    Io(io::Error),
+    /// A line is not a word of `LETTERS` letters.
+    BadLine { line: usize, text: String },
+    /// The list has no words in it.
+    Empty,
+}
+
+impl fmt::Display for WordsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WordsError::Io(ref err) => write!(f, "could not read the word list: {}", err),
+            WordsError::BadLine { line, ref text } => {
+                write!(f, "line {} of the word list is not a {} letter word: '{}'",
+                       line, LETTERS, text)
+            }
+            WordsError::Empty => write!(f, "the word list has no words in it"),
+        }
+    }
+}
+
+impl Error for WordsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            WordsError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WordsError {
+    fn from(err: io::Error) -> WordsError {
+        WordsError::Io(err)
+    }
+}
+
+/// The words that can be the secret, and that are accepted as guesses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordList {
+    /// Lower case and sorted, without duplicates.
+    words: Vec<String>,
+}
+
+impl WordList {
+    /// The list that comes with the game.
+    pub fn builtin() -> WordList {
+        WordList::from_reader(BUILTIN.as_bytes()).expect("the built-in word list is valid")
+    }
+
+    /// Reads one word per line. Blank lines and lines starting with `#`
+    /// are skipped, and case doesn't matter.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<WordList, WordsError> {
+        let mut words = Vec::new();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let text = line.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            if text.len() != LETTERS || !text.bytes().all(|b| b.is_ascii_alphabetic()) {
+                return Err(WordsError::BadLine { line: i + 1, text: text.to_string() });
+            }
+            words.push(text.to_ascii_lowercase());
+        }
+        if words.is_empty() {
+            return Err(WordsError::Empty);
+        }
+
+        words.sort();
+        words.dedup();
+        Ok(WordList { words })
+    }
+
+    /// Reads the word list at `path`, see `from_reader`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<WordList, WordsError> {
+        WordList::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Always false, a list can't be loaded without words.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.binary_search_by(|w| w[..].cmp(word)).is_ok()
+    }
+}
+
+/// What a guess says about one letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// In the word, at this place.
+    Right,
+    /// In the word, somewhere else.
+    Elsewhere,
+    /// Not in the word, or not as often as the guess has it.
+    Absent,
+}
+
+impl Mark {
+    pub fn symbol(self) -> char {
+        match self {
+            Mark::Right => '=',
+            Mark::Elsewhere => '~',
+            Mark::Absent => '.',
+        }
+    }
+}
+
+/// Marks each letter of `guess` against `secret`. Both are lower case
+/// words of `LETTERS` letters.
+///
+/// A letter that appears more often in the guess than in the secret is
+/// only marked as often as the secret has it, right places first.
+pub fn marks(secret: &str, guess: &str) -> [Mark; LETTERS] {
+    let secret = secret.as_bytes();
+    let guess = guess.as_bytes();
+    let mut marks = [Mark::Absent; LETTERS];
+    let mut unmatched = [0u8; 26];
+
+    for i in 0..LETTERS {
+        if guess[i] == secret[i] {
+            marks[i] = Mark::Right;
+        } else {
+            unmatched[(secret[i] - b'a') as usize] += 1;
+        }
+    }
+    for i in 0..LETTERS {
+        let left = &mut unmatched[(guess[i] - b'a') as usize];
+        if marks[i] != Mark::Right && *left > 0 {
+            marks[i] = Mark::Elsewhere;
+            *left -= 1;
+        }
+    }
+    marks
+}
+
+/// A game of guessing a word from a `WordList`.
+pub struct WordPuzzle {
+    words: WordList,
+    secret: String,
+    max_attempts: u32,
+    attempts: u32,
+    catalog: Catalog,
+}
+
+impl WordPuzzle {
+    /// # Panics
+    /// Panics if `secret` isn't in `words` or `max_attempts` is 0.
+    pub fn new(words: WordList, secret: &str, max_attempts: u32) -> WordPuzzle {
+        assert!(words.contains(secret), "the secret must be in the word list");
+        assert!(max_attempts > 0, "a game needs at least one attempt");
+        WordPuzzle {
+            secret: secret.to_string(),
+            words,
+            max_attempts,
+            attempts: 0,
+            catalog: Catalog::english().clone(),
+        }
+    }
+
+    /// The same game, talking in the language of `catalog`.
+    pub fn with_catalog(self, catalog: Catalog) -> WordPuzzle {
+        WordPuzzle { catalog, ..self }
+    }
+
+    /// Starts a game with a word from `words`, picked by its place in the
+    /// sorted list as drawn from `source`.
+    pub fn draw<S: SecretSource + ?Sized>(source: &mut S, words: WordList, max_attempts: u32)
+        -> Result<WordPuzzle, SecretError>
+    {
+        let rules = Rules { min: 0, max: words.len() as u32 - 1, max_attempts: None };
+        let i = source.next_secret(&rules)? as usize;
+        let secret = words.words[i].clone();
+        Ok(WordPuzzle::new(words, &secret, max_attempts))
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
+
+impl Puzzle for WordPuzzle {
+    type Guess = String;
+    type Feedback = [Mark; LETTERS];
+
+    fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    fn intro(&self) -> String {
+        format!("{}\n{}", self.catalog.get("words_title"),
+                self.catalog.format("words_rules", &[("letters", &LETTERS)]))
+    }
+
+    fn parse_guess(&self, line: &str) -> Result<String, String> {
+        let word = line.trim().to_ascii_lowercase();
+        if word.len() != LETTERS || !word.bytes().all(|b| b.is_ascii_alphabetic()) {
+            return Err(self.catalog.format("words_bad_length", &[("letters", &LETTERS)]));
+        }
+        if !self.words.contains(&word) {
+            return Err(self.catalog.format("words_unknown", &[("word", &word)]));
+        }
+        Ok(word)
+    }
+
+    fn guess(&mut self, guess: &String) -> [Mark; LETTERS] {
+        self.attempts += 1;
+        marks(&self.secret, guess)
+    }
+
+    fn is_win(&self, feedback: &[Mark; LETTERS]) -> bool {
+        feedback.iter().all(|&m| m == Mark::Right)
+    }
+
+    fn show(&self, guess: &String, feedback: &[Mark; LETTERS]) -> String {
+        let guessed = self.catalog.format("guessed", &[("guess", guess)]);
+        if self.is_win(feedback) {
+            return format!("{}\n{}", guessed, self.catalog.get("correct"));
+        }
+        let symbols: String = feedback.iter().map(|m| m.symbol()).collect();
+        // Line the marks up under the letters, which end the line.
+        let indent = guessed.chars().count() - LETTERS;
+        format!("{}\n{:indent$}{}", guessed, "", symbols, indent = indent)
+    }
+
+    fn attempts_left(&self) -> Option<u32> {
+        Some(self.max_attempts - self.attempts)
+    }
+
+    fn reveal(&self) -> String {
+        self.catalog.format("words_reveal", &[("word", &self.secret)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::Status;
+    use puzzle;
+    use secret::FixedSource;
+    use std::io::Cursor;
+
+    fn list(words: &str) -> WordList {
+        WordList::from_reader(words.as_bytes()).unwrap()
+    }
+
+    fn symbols(secret: &str, guess: &str) -> String {
+        marks(secret, guess).iter().map(|m| m.symbol()).collect()
+    }
+
+    #[test]
+    fn builtin_list() {
+        let words = WordList::builtin();
+        assert!(words.len() > 100);
+        assert!(words.contains("crane"));
+        assert!(!words.contains("crank"));
+    }
+
+    #[test]
+    fn loading() {
+        let words = list("# comment\n\nCrane\nslate\ncrane\n");
+        assert_eq!(2, words.len());
+        assert!(words.contains("crane"));
+
+        match WordList::from_reader("crane\ncranes\n".as_bytes()) {
+            Err(WordsError::BadLine { line: 2, ref text }) if text == "cranes" => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match WordList::from_reader("# nothing\n".as_bytes()) {
+            Err(WordsError::Empty) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn marking() {
+        assert_eq!("=====", symbols("crane", "crane"));
+        assert_eq!(".....", symbols("crane", "idiot"));
+        assert_eq!("=..~~", symbols("crane", "cigar"));
+        assert_eq!("~~~~~", symbols("abcde", "eabcd"));
+        // One 'e' in the secret: the right place wins, the others are absent.
+        assert_eq!("....=", symbols("crane", "geese"));
+        // Three 'l's in the guess, one in the secret: only the first counts.
+        assert_eq!("~....", symbols("plate", "lolly"));
+    }
+
+    #[test]
+    fn parse() {
+        let game = WordPuzzle::new(list("crane\nslate\n"), "crane", 6);
+        assert_eq!(Ok("slate".to_string()), game.parse_guess(" SLATE "));
+        assert_eq!(Err("Please type a word of 5 letters.".to_string()), game.parse_guess("cat"));
+        assert_eq!(Err("'zzzzz' is not in the word list.".to_string()), game.parse_guess("zzzzz"));
+    }
+
+    #[test]
+    fn draw() {
+        let game = WordPuzzle::draw(&mut FixedSource(1), list("slate\ncrane\n"), 6).unwrap();
+        assert_eq!("The word was slate.", game.reveal());
+        assert!(WordPuzzle::draw(&mut FixedSource(2), list("slate\ncrane\n"), 6).is_err());
+    }
+
+    #[test]
+    fn plays_through_the_driver() {
+        let mut game = WordPuzzle::new(list("crane\nslate\nplate\n"), "plate", 2);
+        let mut output = Vec::new();
+        let status = puzzle::play(&mut game, &mut Cursor::new("crane\nslate\n"), &mut output);
+
+        assert_eq!(Status::Lost, status.unwrap());
+        assert!(String::from_utf8(output).unwrap().ends_with("\
+Please input your guess.
+You guessed: crane
+             ..=.=
+1 attempt(s) left.
+Please input your guess.
+You guessed: slate
+             .====
+You lose! The word was plate.
+"));
+    }
+
+    #[test]
+    fn talks_in_other_languages() {
+        let game = WordPuzzle::new(list("crane\nslate\n"), "crane", 6)
+            .with_catalog(Catalog::new("de").unwrap());
+        assert_eq!(Err("'zzzzz' steht nicht in der Wortliste.".to_string()),
+                   game.parse_guess("zzzzz"));
+        // The marks still line up under the letters.
+        assert_eq!("Dein Tipp: slate\n           ..=.=",
+                   game.show(&"slate".to_string(), &marks("crane", "slate")));
+    }
+}
@@ -0,0 +1,704 @@
+//! Recording games to a file, and playing them back.
+//!
+//! A recording is a JSON-lines file: one `Event` per line, starting with
+//! `Event::Start`. The secret itself is not in it, so a recording can be
+//! shared, even while the game is still going, without giving the answer
+//! away. The start event only holds a hash of the secret with a salt
+//! drawn for the recording.
+//!
+//! `replay` takes the feedback from the recording. If the game was won,
+//! the winning guess must match the hash, and is then played against as
+//! the secret: every other guess must get the feedback it got the first
+//! time, and every hint the same answer. Without a winning guess there
+//! is nothing to check the hash against; the recorded feedback must
+//! still fit some secret in the range, and hints are answered as
+//! recorded.
+
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand;
+use serde_json;
+
+use game::{Feedback, Game, Rules, Status};
+use hints::{Hint, Hints};
+use i18n::Catalog;
+use puzzle::{NumberPuzzle, Puzzle};
+use scores;
+
+/// One line of a recording. `at` is the time in seconds since the start.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// The game began. Always the first event.
+    Start {
+        /// Drawn anew for every recording.
+        salt: String,
+        /// `hash` of the salt and the secret.
+        hash: String,
+        min: u32,
+        max: u32,
+        max_attempts: Option<u32>,
+        /// Names of the hints that could be bought.
+        hints: Vec<String>,
+        /// The code of the language the game talked in, such as "de".
+        lang: String,
+        /// Seconds since the Unix epoch.
+        started_at: u64,
+    },
+    /// The player was asked for a line.
+    Prompt { at: f64, text: String },
+    /// The player typed a line, shown without its line ending.
+    Input { at: f64, line: String },
+    /// The line before was a command, such as "hint parity", and this is
+    /// what the game answered.
+    Reply { at: f64, text: String },
+    /// The line before was a guess, and this is what the game said.
+    Guess { at: f64, guess: u32, feedback: Feedback },
+    /// The game is over. Missing if the input closed before that.
+    End { at: f64, status: Status },
+}
+
+impl Event {
+    /// When the event happened, in seconds since the start.
+    pub fn at(&self) -> f64 {
+        match *self {
+            Event::Start { .. } => 0.0,
+            Event::Prompt { at, .. } | Event::Input { at, .. } | Event::Reply { at, .. } |
+            Event::Guess { at, .. } | Event::End { at, .. } => at,
+        }
+    }
+}
+
+/// The hash of `secret` with `salt` for `Event::Start`, as 16 hex digits.
+///
+/// This is FNV-1a of "salt:secret", which, unlike the hasher in the
+/// standard library, is guaranteed to give the same hash in every build.
+pub fn hash(salt: &str, secret: u32) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in format!("{}:{}", salt, secret).bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Why a recording could not be written, read or replayed.
+#[derive(Debug)]
+pub enum RecordError {
+    /// Reading or writing the file failed.
+    Io(io::Error),
+    /// A line of the file is not an event.
+    Corrupt { line: usize, err: serde_json::Error },
+    /// The first event is not a start event, or there is another one later.
+    BadStart(String),
+    /// The winning guess doesn't match the hash of the secret, or is
+    /// outside the range.
+    BadSecret,
+    /// The game now answers differently from the recording, at the
+    /// event on line `line`.
+    Mismatch { line: usize, recorded: String, replayed: String },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecordError::Io(ref err) => write!(f, "could not access the recording: {}", err),
+            RecordError::Corrupt { line, ref err } => {
+                write!(f, "line {} of the recording is not an event: {}", line, err)
+            }
+            RecordError::BadStart(ref why) => write!(f, "bad recording: {}", why),
+            RecordError::BadSecret => {
+                write!(f, "the winning guess of the recording doesn't match its hash or range")
+            }
+            RecordError::Mismatch { line, ref recorded, ref replayed } => {
+                write!(f, "line {}: the recording says {}, but the game now says {}",
+                       line, recorded, replayed)
+            }
+        }
+    }
+}
+
+impl Error for RecordError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            RecordError::Io(ref err) => Some(err),
+            RecordError::Corrupt { ref err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RecordError {
+    fn from(err: io::Error) -> RecordError {
+        RecordError::Io(err)
+    }
+}
+
+/// A number game that writes everything that happens in it to `out`.
+///
+/// The puzzle methods can't fail, so the first write error is kept and
+/// handed back by `finish`. Nothing is written after it.
+pub struct Recorder<W: Write> {
+    number: NumberPuzzle,
+    out: RefCell<W>,
+    started: Instant,
+    error: RefCell<Option<io::Error>>,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Starts recording `number`, which should not have been played yet.
+    ///
+    /// # Failures
+    /// Returns the error if the start event can't be written.
+    pub fn new(number: NumberPuzzle, out: W) -> io::Result<Recorder<W>> {
+        let recorder = Recorder {
+            out: RefCell::new(out),
+            started: Instant::now(),
+            error: RefCell::new(None),
+            number,
+        };
+
+        let game = recorder.number.game();
+        let rules = game.rules();
+        let salt = format!("{:016x}", rand::random::<u64>());
+        recorder.write(&Event::Start {
+            hash: hash(&salt, game.secret()),
+            salt,
+            min: rules.min,
+            max: rules.max,
+            max_attempts: rules.max_attempts,
+            hints: recorder.number.hints().allowed().iter().map(|h| h.name().to_string()).collect(),
+            lang: recorder.number.catalog().lang().to_string(),
+            started_at: scores::now(),
+        });
+        let error = recorder.error.borrow_mut().take();
+        match error {
+            Some(err) => Err(err),
+            None => Ok(recorder),
+        }
+    }
+
+    /// Seconds since the start.
+    fn at(&self) -> f64 {
+        self.started.elapsed().as_secs_f64()
+    }
+
+    fn write(&self, event: &Event) {
+        if self.error.borrow().is_some() {
+            return;
+        }
+        // An event always serializes: it has no maps with odd keys.
+        let mut line = serde_json::to_string(event).expect("events serialize");
+        line.push('\n');
+
+        let mut out = self.out.borrow_mut();
+        if let Err(err) = out.write_all(line.as_bytes()).and_then(|_| out.flush()) {
+            *self.error.borrow_mut() = Some(err);
+        }
+    }
+
+    /// Records the end of the game and gives it back.
+    ///
+    /// # Failures
+    /// Returns the first error writing the recording, next to the game.
+    pub fn finish(self, status: Status) -> (NumberPuzzle, io::Result<()>) {
+        self.write(&Event::End { at: self.at(), status });
+        let result = match self.error.into_inner() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        };
+        (self.number, result)
+    }
+}
+
+impl<W: Write> Puzzle for Recorder<W> {
+    type Guess = u32;
+    type Feedback = Feedback;
+
+    fn catalog(&self) -> &Catalog {
+        self.number.catalog()
+    }
+
+    fn intro(&self) -> String {
+        self.number.intro()
+    }
+
+    fn prompt(&self) -> String {
+        let text = self.number.prompt();
+        self.write(&Event::Prompt { at: self.at(), text: text.clone() });
+        text
+    }
+
+    fn parse_guess(&self, line: &str) -> Result<u32, String> {
+        self.number.parse_guess(line)
+    }
+
+    /// Every line the player types comes through here first, so this is
+    /// where it is recorded.
+    fn command(&mut self, line: &str) -> Option<String> {
+        let raw = line.trim_end_matches(['\r', '\n']).to_string();
+        self.write(&Event::Input { at: self.at(), line: raw });
+        let reply = self.number.command(line);
+        if let Some(ref text) = reply {
+            self.write(&Event::Reply { at: self.at(), text: text.clone() });
+        }
+        reply
+    }
+
+    fn guess(&mut self, guess: &u32) -> Feedback {
+        let feedback = self.number.guess(guess);
+        self.write(&Event::Guess { at: self.at(), guess: *guess, feedback });
+        feedback
+    }
+
+    fn is_win(&self, feedback: &Feedback) -> bool {
+        self.number.is_win(feedback)
+    }
+
+    fn show(&self, guess: &u32, feedback: &Feedback) -> String {
+        self.number.show(guess, feedback)
+    }
+
+    fn attempts_left(&self) -> Option<u32> {
+        self.number.attempts_left()
+    }
+
+    fn reveal(&self) -> String {
+        self.number.reveal()
+    }
+}
+
+/// Reads a recording, one event per line. Blank lines are skipped.
+pub fn load<R: BufRead>(reader: R) -> Result<Vec<Event>, RecordError> {
+    let mut events = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line)
+            .map_err(|err| RecordError::Corrupt { line: i + 1, err })?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Reads the recording at `path`, see `load`.
+pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Vec<Event>, RecordError> {
+    load(BufReader::new(File::open(path)?))
+}
+
+/// The catalog of the language `events` were recorded in, English if
+/// there is none for it.
+pub fn catalog(events: &[Event]) -> Catalog {
+    match events.first() {
+        Some(Event::Start { lang, .. }) => Catalog::choose(Some(lang)),
+        _ => Catalog::english().clone(),
+    }
+}
+
+/// How a guess and its feedback read in a mismatch message.
+fn describe(guess: u32, feedback: Feedback) -> String {
+    let verdict = match feedback {
+        Feedback::TooSmall => "too small",
+        Feedback::TooBig => "too big",
+        Feedback::Correct => "correct",
+        Feedback::OutOfRange => "out of range",
+    };
+    format!("{} is {}", guess, verdict)
+}
+
+/// How the answer to a command reads in a mismatch message.
+fn quote(text: &str) -> String {
+    format!("the answer {:?}", text)
+}
+
+/// What the game answered that the recording hasn't confirmed yet, for
+/// a mismatch message.
+fn pending(guess: Option<(u32, Feedback)>, answer: &Option<String>) -> String {
+    match (guess, answer) {
+        (Some((guess, feedback)), _) => describe(guess, feedback),
+        (None, Some(text)) => quote(text),
+        (None, None) => "nothing".to_string(),
+    }
+}
+
+/// The secret to play `events` back against, and whether it is the real
+/// one, which it is if the game was won.
+///
+/// Otherwise it is the smallest number the recorded feedback allows. If
+/// the feedback allows none, playing against it shows where it went wrong.
+///
+/// # Failures
+/// Returns `RecordError::BadSecret` if the winning guess doesn't match
+/// `hash` or `rules`.
+fn secret(events: &[Event], salt: &str, hash: &str, rules: &Rules) -> Result<(u32, bool), RecordError> {
+    let won = events.iter().find_map(|event| match *event {
+        Event::Guess { guess, feedback: Feedback::Correct, .. } => Some(guess),
+        _ => None,
+    });
+    if let Some(guess) = won {
+        if self::hash(salt, guess) != hash || !rules.contains(guess) {
+            return Err(RecordError::BadSecret);
+        }
+        return Ok((guess, true));
+    }
+
+    let low = events.iter()
+        .filter_map(|event| match *event {
+            Event::Guess { guess, feedback: Feedback::TooSmall, .. } => Some(guess.saturating_add(1)),
+            _ => None,
+        })
+        .fold(rules.min, u32::max);
+    Ok((low.min(rules.max), false))
+}
+
+/// Plays `events` back into a fresh game, writing the session to `out`
+/// in the language it was recorded in.
+///
+/// The pauses between events are kept, divided by `speed`; a `speed` of
+/// 0 plays everything at once. `line` numbers in errors count events,
+/// which are the lines of a file without blank lines.
+///
+/// Returns how the replayed game ended.
+///
+/// # Failures
+/// Returns `RecordError::BadSecret` if the winning guess doesn't match
+/// the hash, `RecordError::Mismatch` as soon as the game answers
+/// something the recording doesn't have, and `RecordError::Io` if
+/// writing fails.
+pub fn replay<W: Write>(events: &[Event], speed: u32, out: &mut W) -> Result<Status, RecordError> {
+    let (secret, known, rules, allowed) = match events.first() {
+        Some(&Event::Start { ref salt, ref hash, min, max, max_attempts, ref hints, .. }) => {
+            let rules = Rules { min, max, max_attempts };
+            rules.validate().map_err(RecordError::BadStart)?;
+            let (secret, known) = self::secret(events, salt, hash, &rules)?;
+            let mut allowed = Vec::new();
+            for name in hints {
+                let hint = Hint::parse(name)
+                    .ok_or_else(|| RecordError::BadStart(format!("unknown hint '{}'", name)))?;
+                allowed.push(hint);
+            }
+            (secret, known, rules, allowed)
+        }
+        _ => return Err(RecordError::BadStart("it doesn't begin with a start event".to_string())),
+    };
+
+    let mut number = NumberPuzzle::new(Game::with_rules(secret, rules), Hints::new(&allowed))
+        .with_catalog(catalog(events));
+    // The last guess the game answered, until the recording confirms it.
+    let mut unconfirmed: Option<(u32, Feedback)> = None;
+    // The same for the answer to a command.
+    let mut answer: Option<String> = None;
+    let mut last = 0.0;
+
+    writeln!(out, "{}", number.intro())?;
+
+    for (i, event) in events.iter().enumerate().skip(1) {
+        let line = i + 1;
+        let unexpected = |guess: Option<(u32, Feedback)>, answer: &Option<String>, recorded: String| {
+            RecordError::Mismatch { line, recorded, replayed: pending(guess, answer) }
+        };
+
+        if speed > 0 && event.at() > last {
+            thread::sleep(Duration::from_secs_f64((event.at() - last) / speed as f64));
+        }
+        last = event.at();
+
+        match *event {
+            Event::Start { .. } => {
+                return Err(RecordError::BadStart(format!("a second start event on line {}", line)))
+            }
+            Event::Prompt { ref text, .. } => writeln!(out, "{}", text)?,
+            Event::Input { line: ref text, .. } => {
+                if unconfirmed.is_some() || answer.is_some() {
+                    return Err(unexpected(unconfirmed, &answer, "nothing".to_string()));
+                }
+                writeln!(out, "> {}", text)?;
+
+                // Without the real secret the answer can't be worked
+                // out, so the recorded one is shown when it comes.
+                if let Some(reply) = number.command(text) {
+                    if known {
+                        writeln!(out, "{}", reply)?;
+                    }
+                    answer = Some(reply);
+                    continue;
+                }
+                match number.parse_guess(text) {
+                    Ok(guess) => {
+                        let feedback = number.guess(&guess);
+                        writeln!(out, "{}", number.show(&guess, &feedback))?;
+                        unconfirmed = Some((guess, feedback));
+                    }
+                    Err(message) => writeln!(out, "{}", message)?,
+                }
+            }
+            Event::Reply { ref text, .. } => {
+                let confirmed = match answer {
+                    Some(ref reply) => !known || reply == text,
+                    None => false,
+                };
+                if !confirmed {
+                    return Err(unexpected(unconfirmed, &answer, quote(text)));
+                }
+                if !known {
+                    writeln!(out, "{}", text)?;
+                }
+                answer = None;
+            }
+            Event::Guess { guess, feedback, .. } => {
+                if unconfirmed != Some((guess, feedback)) {
+                    return Err(unexpected(unconfirmed, &answer, describe(guess, feedback)));
+                }
+                unconfirmed = None;
+            }
+            Event::End { status, .. } => {
+                if unconfirmed.is_some() || answer.is_some() {
+                    return Err(unexpected(unconfirmed, &answer, "nothing".to_string()));
+                }
+                let now = number.game().status();
+                if status != now {
+                    return Err(RecordError::Mismatch {
+                        line,
+                        recorded: format!("the game ended {:?}", status),
+                        replayed: format!("{:?}", now),
+                    });
+                }
+            }
+        }
+    }
+
+    if unconfirmed.is_some() || answer.is_some() {
+        return Err(RecordError::Mismatch {
+            line: events.len() + 1,
+            recorded: "nothing".to_string(),
+            replayed: pending(unconfirmed, &answer),
+        });
+    }
+    Ok(number.game().status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use puzzle;
+    use std::io::Cursor;
+
+    fn number() -> NumberPuzzle {
+        let rules = Rules { min: 1, max: 100, max_attempts: Some(5) };
+        NumberPuzzle::new(Game::with_rules(42, rules), Hints::new(&[Hint::Parity]))
+    }
+
+    /// Plays `input` with a recorder and returns the recording.
+    fn record(input: &str) -> Vec<Event> {
+        record_game(number(), input)
+    }
+
+    fn record_game(number: NumberPuzzle, input: &str) -> Vec<Event> {
+        let mut file = Vec::new();
+        {
+            let mut recorder = Recorder::new(number, &mut file).unwrap();
+            // Running out of input leaves the game unfinished.
+            let status = puzzle::play(&mut recorder, &mut Cursor::new(input), &mut io::sink())
+                .unwrap_or(Status::Playing);
+            assert!(recorder.finish(status).1.is_ok());
+        }
+        load(&file[..]).unwrap()
+    }
+
+    #[test]
+    fn hashes() {
+        assert_eq!(16, hash("salt", 42).len());
+        assert_ne!(hash("salt", 42), hash("salt", 43));
+        assert_ne!(hash("salt", 42), hash("pepper", 42));
+    }
+
+    #[test]
+    fn records_every_event() {
+        let events = record("hint parity\nabc\n50\n42\n");
+
+        match events[0] {
+            Event::Start { ref salt, ref hash, min: 1, max: 100, max_attempts: Some(5), ref hints, .. } => {
+                assert_eq!(&super::hash(salt, 42), hash);
+                assert_eq!(&["parity".to_string()], &hints[..]);
+            }
+            ref other => panic!("unexpected {:?}", other),
+        }
+        // The secret is nowhere near the start, and every recording is
+        // salted differently.
+        assert!(!serde_json::to_string(&events[0]).unwrap().contains("secret"));
+        assert_ne!(events[0], record("")[0]);
+
+        let kinds: Vec<String> = events[1..].iter()
+            .map(|e| match *e {
+                Event::Prompt { .. } => "prompt".to_string(),
+                Event::Input { ref line, .. } => format!("input {}", line),
+                Event::Reply { ref text, .. } => format!("reply {}", text),
+                Event::Guess { guess, feedback, .. } => describe(guess, feedback),
+                Event::End { status, .. } => format!("end {:?}", status),
+                Event::Start { .. } => "start".to_string(),
+            })
+            .collect();
+        assert_eq!(kinds, [
+            "prompt", "input hint parity", "reply The number is even. (-10 points)",
+            "prompt", "input abc", "prompt", "input 50",
+            "50 is too big", "prompt", "input 42", "42 is correct", "end Won",
+        ]);
+        assert!(events.windows(2).all(|w| w[0].at() <= w[1].at()));
+    }
+
+    #[test]
+    fn replays_the_same_game() {
+        let events = record("hint parity\n50\n42\n");
+        let mut out = Vec::new();
+        assert_eq!(Status::Won, replay(&events, 0, &mut out).unwrap());
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("> hint parity\nThe number is even. (-10 points)\n"), "{}", out);
+        assert!(out.ends_with("> 42\nYou guessed: 42\nYou win!\n"), "{}", out);
+
+        // Unfinished games replay too.
+        let events = record("50\n");
+        assert_eq!(Status::Playing, replay(&events, 0, &mut io::sink()).unwrap());
+    }
+
+    #[test]
+    fn replays_games_that_were_not_won() {
+        let events = record("hint parity\n50\n10\n30\n45\n41\n");
+        let mut out = Vec::new();
+        assert_eq!(Status::Lost, replay(&events, 0, &mut out).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("> hint parity\nThe number is even. (-10 points)\n"), "{}", out);
+        assert!(out.ends_with("> 41\nYou guessed: 41\nToo small!\n"), "{}", out);
+
+        // Feedback that fits no secret: 10 can't be too big when 30 is
+        // too small.
+        let mut events = events;
+        for event in &mut events {
+            if let Event::Guess { guess: 10, ref mut feedback, .. } = *event {
+                *feedback = Feedback::TooBig;
+            }
+        }
+        let err = replay(&events, 0, &mut io::sink()).unwrap_err();
+        assert!(err.to_string().ends_with("the recording says 10 is too big, but the game now says 10 is too small"),
+                "{}", err);
+    }
+
+    #[test]
+    fn replays_in_the_recorded_language() {
+        let events = record_game(number().with_catalog(Catalog::new("de").unwrap()), "50\n42\n");
+        match events[0] {
+            Event::Start { ref lang, .. } => assert_eq!("de", lang),
+            ref other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!("de", catalog(&events).lang());
+
+        let mut out = Vec::new();
+        assert_eq!(Status::Won, replay(&events, 0, &mut out).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("> 50\nDein Tipp: 50\nZu groß!\n"), "{}", out);
+        assert!(!out.contains("You guessed"), "{}", out);
+    }
+
+    #[test]
+    fn replays_wide_ranges_at_once() {
+        let rules = Rules { min: 0, max: 4_000_000_000, max_attempts: None };
+        let number = NumberPuzzle::new(Game::with_rules(3_999_999_999, rules), Hints::new(&[]));
+        let events = record_game(number, "2000000000\n3999999999\n");
+
+        let started = Instant::now();
+        assert_eq!(Status::Won, replay(&events, 0, &mut io::sink()).unwrap());
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn catches_a_different_answer() {
+        let mut events = record("50\n42\n");
+        for event in &mut events {
+            if let Event::Guess { guess: 50, ref mut feedback, .. } = *event {
+                *feedback = Feedback::TooSmall;
+            }
+        }
+        let err = replay(&events, 0, &mut io::sink()).unwrap_err();
+        assert_eq!("line 4: the recording says 50 is too small, but the game now says 50 is too big",
+                   err.to_string());
+
+        // A guess the recording left out.
+        let events: Vec<Event> = record("50\n42\n").into_iter()
+            .filter(|e| !matches!(*e, Event::Guess { guess: 50, .. }))
+            .collect();
+        match replay(&events, 0, &mut io::sink()) {
+            Err(RecordError::Mismatch { line: 5, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // A hint answered differently.
+        let mut events = record("hint parity\n42\n");
+        if let Event::Reply { ref mut text, .. } = events[3] {
+            *text = "The number is odd. (-10 points)".to_string();
+        }
+        let err = replay(&events, 0, &mut io::sink()).unwrap_err();
+        assert_eq!("line 4: the recording says the answer \"The number is odd. (-10 points)\", \
+                    but the game now says the answer \"The number is even. (-10 points)\"",
+                   err.to_string());
+    }
+
+    #[test]
+    fn bad_files() {
+        match load("{\"event\":\"prompt\",\"at\":0.0,\"text\":\"?\"}\n\nnot json\n".as_bytes()) {
+            Err(RecordError::Corrupt { line: 3, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        let events = vec![Event::Prompt { at: 0.0, text: "?".to_string() }];
+        match replay(&events, 0, &mut io::sink()) {
+            Err(RecordError::BadStart(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // The winning guess changed by hand, and a hash to go with a
+        // winning guess that doesn't fit the range.
+        let won = record("42\n");
+        let mut events = won.clone();
+        for event in &mut events {
+            match *event {
+                Event::Input { ref mut line, .. } => *line = "43".to_string(),
+                Event::Guess { ref mut guess, .. } => *guess = 43,
+                _ => {}
+            }
+        }
+        match replay(&events, 0, &mut io::sink()) {
+            Err(RecordError::BadSecret) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        for event in &mut events {
+            match *event {
+                Event::Start { ref salt, ref mut hash, .. } => *hash = super::hash(salt, 500),
+                Event::Input { ref mut line, .. } => *line = "500".to_string(),
+                Event::Guess { ref mut guess, .. } => *guess = 500,
+                _ => {}
+            }
+        }
+        match replay(&events, 0, &mut io::sink()) {
+            Err(RecordError::BadSecret) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Another salt doesn't fit the hash either.
+        let mut events = won;
+        if let Event::Start { ref mut salt, .. } = events[0] {
+            salt.push('0');
+        }
+        match replay(&events, 0, &mut io::sink()) {
+            Err(RecordError::BadSecret) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
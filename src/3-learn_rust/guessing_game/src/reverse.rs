@@ -0,0 +1,243 @@
+//! The game with the roles swapped: the player thinks of a number and the
+//! computer guesses it.
+//!
+//! The player answers with the same vocabulary the game uses the other way
+//! round, so every answer becomes a `Feedback` on the computer's guess.
+//! The computer narrows the interval of numbers still possible. If the
+//! answers contradict each other the interval ends up empty, and the
+//! computer knows it has been lied to.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use game::{Feedback, Rules};
+use i18n::Catalog;
+use solver::Interval;
+
+/// What the player says about the computer's guess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    /// The player's number is higher than the guess.
+    Higher,
+    /// The player's number is lower than the guess.
+    Lower,
+    /// The guess is the player's number.
+    Correct,
+}
+
+impl Answer {
+    /// Understands "higher"/"lower"/"correct", their first letters, and
+    /// the symbols `>`, `<` and `=`. Case and surrounding space don't matter.
+    pub fn parse(line: &str) -> Option<Answer> {
+        match &line.trim().to_lowercase()[..] {
+            "higher" | "h" | ">" | "+" => Some(Answer::Higher),
+            "lower" | "l" | "<" | "-" => Some(Answer::Lower),
+            "correct" | "c" | "=" | "yes" | "y" => Some(Answer::Correct),
+            _ => None,
+        }
+    }
+
+    /// How the player's number compares with the guess.
+    pub fn ordering(self) -> Ordering {
+        match self {
+            Answer::Higher => Ordering::Greater,
+            Answer::Lower => Ordering::Less,
+            Answer::Correct => Ordering::Equal,
+        }
+    }
+}
+
+impl From<Answer> for Feedback {
+    /// The feedback the game would have given on the guess: "higher"
+    /// means the guess was too small.
+    fn from(answer: Answer) -> Feedback {
+        Feedback::from(answer.ordering().reverse())
+    }
+}
+
+/// The answers that can't all be true.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contradiction {
+    /// Biggest guess the player called too small, if any.
+    pub too_small: Option<u32>,
+    /// Smallest guess the player called too big, if any.
+    pub too_big: Option<u32>,
+    /// The range the number was supposed to be in.
+    pub min: u32,
+    pub max: u32,
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::english().contradiction(self))
+    }
+}
+
+/// Where a reverse game stands after an answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The computer needs another guess.
+    Continue,
+    /// The computer guessed the number.
+    Found(u32),
+    /// The answers contradict each other.
+    Cheated(Contradiction),
+}
+
+/// The computer's side of a reverse game.
+#[derive(Debug)]
+pub struct ReverseGame {
+    rules: Rules,
+    interval: Interval,
+    guesses: u32,
+    too_small: Option<u32>,
+    too_big: Option<u32>,
+}
+
+impl ReverseGame {
+    /// Starts guessing a number within the range of `rules`.
+    pub fn new(rules: Rules) -> ReverseGame {
+        ReverseGame {
+            rules,
+            interval: Interval::new(&rules),
+            guesses: 0,
+            too_small: None,
+            too_big: None,
+        }
+    }
+
+    /// The computer's current guess: the middle of what is still possible.
+    pub fn guess(&self) -> u32 {
+        self.interval.middle()
+    }
+
+    /// How many of the computer's guesses have been answered.
+    pub fn guesses(&self) -> u32 {
+        self.guesses
+    }
+
+    /// The numbers the player's number can still be.
+    pub fn interval(&self) -> Interval {
+        self.interval
+    }
+
+    /// Takes the player's answer to the current guess.
+    pub fn answer(&mut self, answer: Answer) -> Verdict {
+        let guess = self.guess();
+        let feedback = Feedback::from(answer);
+        self.guesses += 1;
+
+        match feedback {
+            Feedback::TooSmall => {
+                self.too_small = Some(self.too_small.map_or(guess, |low| low.max(guess)))
+            }
+            Feedback::TooBig => {
+                self.too_big = Some(self.too_big.map_or(guess, |high| high.min(guess)))
+            }
+            Feedback::Correct => return Verdict::Found(guess),
+            Feedback::OutOfRange => unreachable!("answers never give OutOfRange"),
+        }
+
+        self.interval.narrow(guess, feedback);
+
+        if self.interval.is_empty() {
+            Verdict::Cheated(Contradiction {
+                too_small: self.too_small,
+                too_big: self.too_big,
+                min: self.rules.min,
+                max: self.rules.max,
+            })
+        } else {
+            Verdict::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(min: u32, max: u32) -> Rules {
+        Rules { min, max, max_attempts: None }
+    }
+
+    /// Answers honestly for `secret` until the game ends.
+    fn play_honestly(game: &mut ReverseGame, secret: u32) -> Verdict {
+        loop {
+            let answer = match secret.cmp(&game.guess()) {
+                Ordering::Greater => Answer::Higher,
+                Ordering::Less => Answer::Lower,
+                Ordering::Equal => Answer::Correct,
+            };
+            match game.answer(answer) {
+                Verdict::Continue => continue,
+                verdict => return verdict,
+            }
+        }
+    }
+
+    #[test]
+    fn parses_answers() {
+        assert_eq!(Some(Answer::Higher), Answer::parse(" Higher\n"));
+        assert_eq!(Some(Answer::Lower), Answer::parse("l"));
+        assert_eq!(Some(Answer::Correct), Answer::parse("="));
+        assert_eq!(None, Answer::parse("maybe"));
+    }
+
+    #[test]
+    fn answers_are_feedback_on_the_guess() {
+        assert_eq!(Feedback::TooSmall, Feedback::from(Answer::Higher));
+        assert_eq!(Feedback::TooBig, Feedback::from(Answer::Lower));
+        assert_eq!(Feedback::Correct, Feedback::from(Answer::Correct));
+    }
+
+    #[test]
+    fn finds_every_number() {
+        for secret in 1..101 {
+            let mut game = ReverseGame::new(rules(1, 100));
+            assert_eq!(Verdict::Found(secret), play_honestly(&mut game, secret));
+            assert!(game.guesses() <= 7);
+        }
+    }
+
+    #[test]
+    fn catches_contradicting_answers() {
+        let mut game = ReverseGame::new(rules(1, 100));
+        assert_eq!(50, game.guess());
+        assert_eq!(Verdict::Continue, game.answer(Answer::Higher));
+        assert_eq!(75, game.guess());
+        assert_eq!(Verdict::Continue, game.answer(Answer::Lower));
+        let mut verdict = Verdict::Continue;
+        while verdict == Verdict::Continue {
+            // Always "lower" is a lie once we are down to 51.
+            verdict = game.answer(Answer::Lower);
+        }
+        match verdict {
+            Verdict::Cheated(contradiction) => {
+                assert_eq!(Some(50), contradiction.too_small);
+                assert_eq!(Some(51), contradiction.too_big);
+                assert_eq!("you said your number is higher than 50 and lower than 51, \
+                            but there is no number in between", contradiction.to_string());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn catches_answers_past_the_range() {
+        let mut game = ReverseGame::new(rules(1, 3));
+        let mut verdict = Verdict::Continue;
+        while verdict == Verdict::Continue {
+            verdict = game.answer(Answer::Higher);
+        }
+        match verdict {
+            Verdict::Cheated(contradiction) => {
+                assert_eq!(Some(3), contradiction.too_small);
+                assert_eq!(None, contradiction.too_big);
+                assert_eq!("you said your number is higher than 3, but it can't be higher than 3",
+                           contradiction.to_string());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
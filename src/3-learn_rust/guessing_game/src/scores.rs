@@ -0,0 +1,331 @@
+//! The local leaderboard.
+//!
+//! Every finished game is appended to a JSON file. The whole file is
+//! rewritten on each save: we write a temporary file next to it and
+//! rename it over the old one, so a crash half way leaves either the old
+//! or the new leaderboard, never a broken one.
+
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json;
+
+/// Name of the scores file in the home directory.
+pub const DEFAULT_FILE_NAME: &str = ".guessing_game_scores.json";
+
+/// Environment variable that overrides where the scores file is.
+pub const PATH_VAR: &str = "GUESSING_GAME_SCORES";
+
+/// One finished game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    /// Who played.
+    pub player: String,
+    /// Range of the secret.
+    pub min: u32,
+    pub max: u32,
+    /// The attempt limit, if there was one.
+    pub max_attempts: Option<u32>,
+    /// Name of the difficulty preset, if one was used.
+    #[serde(default)]
+    pub difficulty: Option<String>,
+    /// Guesses made.
+    pub attempts: u32,
+    /// Whether the secret was found.
+    pub won: bool,
+    /// Points scored, see `hints::Scorecard`.
+    #[serde(default)]
+    pub points: Option<u32>,
+    /// Time from the first prompt to the end of the game, in seconds.
+    pub elapsed_secs: f64,
+    /// When the game ended, in seconds since the Unix epoch.
+    pub finished_at: u64,
+}
+
+/// The layout of the scores file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScoresFile {
+    scores: Vec<Score>,
+}
+
+/// Why the scores file could not be used.
+#[derive(Debug)]
+pub enum ScoresError {
+    /// Reading or writing the file failed.
+    Io(io::Error),
+    /// The file is not a valid scores file.
+    Corrupt(serde_json::Error),
+}
+
+impl fmt::Display for ScoresError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ScoresError::Io(ref err) => write!(f, "could not access the scores file: {}", err),
+            ScoresError::Corrupt(ref err) => write!(f, "the scores file is damaged: {}", err),
+        }
+    }
+}
+
+impl Error for ScoresError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ScoresError::Io(ref err) => Some(err),
+            ScoresError::Corrupt(ref err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for ScoresError {
+    fn from(err: io::Error) -> ScoresError {
+        ScoresError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ScoresError {
+    fn from(err: serde_json::Error) -> ScoresError {
+        ScoresError::Corrupt(err)
+    }
+}
+
+/// Where the scores file is: `$GUESSING_GAME_SCORES` if set, otherwise
+/// `DEFAULT_FILE_NAME` in the home directory, or in the current
+/// directory if there is no home.
+pub fn default_path() -> PathBuf {
+    if let Some(path) = env::var_os(PATH_VAR) {
+        return PathBuf::from(path);
+    }
+    match env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")) {
+        Some(home) => Path::new(&home).join(DEFAULT_FILE_NAME),
+        None => PathBuf::from(DEFAULT_FILE_NAME),
+    }
+}
+
+/// Seconds since the Unix epoch, for `Score::finished_at`.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Reads all scores. A missing file is an empty leaderboard.
+pub fn load(path: &Path) -> Result<Vec<Score>, ScoresError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let contents: ScoresFile = serde_json::from_reader(BufReader::new(file))?;
+    Ok(contents.scores)
+}
+
+/// Replaces the scores file with `scores`, atomically.
+pub fn save(path: &Path, scores: &[Score]) -> Result<(), ScoresError> {
+    let contents = ScoresFile { scores: scores.to_vec() };
+
+    // The temporary file has to be in the same directory, renames across
+    // file systems are not atomic. The process id keeps two games
+    // finishing at once from writing the same temporary file.
+    let mut tmp_name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    tmp_name.push(format!(".{}.tmp", process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        serde_json::to_writer_pretty(&mut file, &contents)?;
+        file.write_all(b"\n")?;
+        // Make sure the data is on disk before the rename makes it visible.
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Adds `score` to the scores file.
+///
+/// Two games finishing at the same moment may still lose one of the two
+/// results, but the file itself is never damaged.
+pub fn record(path: &Path, score: Score) -> Result<(), ScoresError> {
+    let mut scores = load(path)?;
+    scores.push(score);
+    save(path, &scores)
+}
+
+/// Which scores to show.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    /// Only games with this range.
+    pub range: Option<(u32, u32)>,
+    /// Only games with this difficulty preset.
+    pub difficulty: Option<String>,
+}
+
+impl Filter {
+    pub fn matches(&self, score: &Score) -> bool {
+        self.range.is_none_or(|range| range == (score.min, score.max)) &&
+            self.difficulty.as_ref().is_none_or(|d| score.difficulty.as_ref() == Some(d))
+    }
+}
+
+/// The best `n` won games that match `filter`: fewest attempts first,
+/// then fastest, then earliest.
+pub fn top<'a>(scores: &'a [Score], filter: &Filter, n: usize) -> Vec<&'a Score> {
+    let mut best: Vec<&Score> = scores.iter().filter(|s| s.won && filter.matches(s)).collect();
+    best.sort_by(|a, b| {
+        a.attempts.cmp(&b.attempts)
+            .then(a.elapsed_secs.total_cmp(&b.elapsed_secs))
+            .then(a.finished_at.cmp(&b.finished_at))
+    });
+    best.truncate(n);
+    best
+}
+
+/// Turns seconds since the Unix epoch into a `YYYY-MM-DD` date (UTC).
+pub fn format_date(secs: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm.
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+                       - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The leaderboard as a table, best first.
+pub fn format_table(scores: &[&Score]) -> String {
+    if scores.is_empty() {
+        return "No scores yet.\n".to_string();
+    }
+
+    let mut table = format!("{:>3}  {:<16} {:>9} {:>10} {:>8} {:>6} {:>9}  {}\n",
+                            "#", "Player", "Range", "Difficulty", "Attempts", "Points", "Time",
+                            "Date");
+    for (i, score) in scores.iter().enumerate() {
+        table.push_str(&format!("{:>3}  {:<16} {:>9} {:>10} {:>8} {:>6} {:>8.1}s  {}\n",
+                                i + 1,
+                                score.player,
+                                format!("{}-{}", score.min, score.max),
+                                score.difficulty.as_ref().map_or("-", |d| &d[..]),
+                                score.attempts,
+                                score.points.map_or("-".to_string(), |p| p.to_string()),
+                                score.elapsed_secs,
+                                format_date(score.finished_at)));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(player: &str, attempts: u32, elapsed_secs: f64) -> Score {
+        Score {
+            player: player.to_string(),
+            min: 1,
+            max: 100,
+            max_attempts: None,
+            difficulty: None,
+            attempts,
+            points: Some(100),
+            won: true,
+            elapsed_secs,
+            finished_at: 1_445_000_000,
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("guessing_game_{}_{}.json", name, process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn missing_file_is_empty() {
+        assert_eq!(Vec::<Score>::new(), load(&temp_path("missing")).unwrap());
+    }
+
+    #[test]
+    fn record_and_load() {
+        let path = temp_path("record");
+        record(&path, score("ada", 5, 10.0)).unwrap();
+        record(&path, score("bob", 4, 12.0)).unwrap();
+        let scores = load(&path).unwrap();
+        assert_eq!(vec![score("ada", 5, 10.0), score("bob", 4, 12.0)], scores);
+
+        // No temporary files are left behind.
+        let dir = path.parent().unwrap();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(fs::read_dir(dir).unwrap().all(|entry| {
+            let entry = entry.unwrap().file_name();
+            let entry = entry.to_str().unwrap();
+            entry == name || !entry.starts_with(name)
+        }));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn damaged_file_is_reported() {
+        let path = temp_path("damaged");
+        fs::write(&path, "{ not json").unwrap();
+        match load(&path) {
+            Err(ScoresError::Corrupt(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        // A failed record leaves the damaged file alone.
+        assert!(record(&path, score("ada", 1, 1.0)).is_err());
+        assert_eq!("{ not json", fs::read_to_string(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn top_sorts_and_filters() {
+        let mut lost = score("loser", 1, 1.0);
+        lost.won = false;
+        let mut small = score("small", 1, 1.0);
+        small.max = 10;
+        let mut hard = score("hard", 3, 9.0);
+        hard.difficulty = Some("hard".to_string());
+        let scores = vec![score("slow", 3, 20.0), score("fast", 3, 5.0), score("best", 2, 30.0),
+                          lost, small, hard];
+
+        let filter = Filter { range: Some((1, 100)), difficulty: None };
+        let names: Vec<&str> = top(&scores, &filter, 3).iter().map(|s| &s.player[..]).collect();
+        assert_eq!(vec!["best", "fast", "hard"], names);
+
+        let filter = Filter { range: None, difficulty: Some("hard".to_string()) };
+        let names: Vec<&str> = top(&scores, &filter, 10).iter().map(|s| &s.player[..]).collect();
+        assert_eq!(vec!["hard"], names);
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!("1970-01-01", format_date(0));
+        assert_eq!("2015-10-16", format_date(1_445_000_000));
+        assert_eq!("2000-02-29", format_date(951_782_400));
+    }
+
+    #[test]
+    fn table() {
+        let best = score("ada", 5, 10.0);
+        let table = format_table(&[&best]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[1].contains("ada"));
+        assert!(lines[1].contains("1-100"));
+        assert!(lines[1].ends_with("10.0s  2015-10-16"));
+        assert_eq!("No scores yet.\n", format_table(&[]));
+    }
+}
@@ -0,0 +1,567 @@
+//! Several players guessing the same secret over TCP.
+//!
+//! The protocol is one line per message. Clients send:
+//!
+//! ```text
+//! JOIN <name>        take part, names have no spaces
+//! GUESS <n>          guess the secret of the current round
+//! LEADERBOARD        ask for the number of rounds everybody has won
+//! QUIT               leave
+//! ```
+//!
+//! The server answers:
+//!
+//! ```text
+//! WELCOME <name> <round> <min> <max>
+//! SMALL | BIG | WIN  the guess was too small, too big or right
+//! LOST               out of attempts for this round
+//! RANGE <min> <max>  the guess was outside the range
+//! LEADERBOARD <name>=<wins> ...
+//! BYE
+//! ERROR <message>
+//! ```
+//!
+//! and tells everyone when a round ends and the next one starts:
+//!
+//! ```text
+//! WINNER <name> <secret>
+//! NOWINNER <secret>
+//! ROUND <round> <min> <max>
+//! ```
+//!
+//! The first correct guess wins the round for its player and starts a
+//! new round with a new secret for everybody. So does the last player
+//! still in the round running out of attempts, with nobody winning. The
+//! secret is only told once the round is over, and a player who lost
+//! stays out of the round even if they leave and join again.
+//!
+//! `Lobby` holds the rules of all this and knows nothing about sockets.
+//! `serve` puts it behind a `TcpListener`, with one thread per client.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use game::{Feedback, Game, Rules, Status};
+use input;
+use secret::{SecretError, SecretSource};
+
+/// Port `serve` and the client use when none is given.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Tells connected clients apart.
+pub type ClientId = usize;
+
+/// A line sent by a client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    Join(String),
+    Guess(u32),
+    Leaderboard,
+    Quit,
+}
+
+impl Request {
+    /// Understands one line of the protocol. Keywords are not case sensitive.
+    ///
+    /// # Failures
+    /// Returns the text of the `ERROR` to send back.
+    pub fn parse(line: &str) -> Result<Request, String> {
+        let line = line.trim();
+        let (keyword, argument) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+
+        match &keyword.to_uppercase()[..] {
+            "JOIN" if argument.is_empty() => Err("JOIN needs a name".to_string()),
+            "JOIN" if argument.contains(char::is_whitespace) || argument.contains('=') => {
+                Err("names can't contain spaces or '='".to_string())
+            }
+            "JOIN" => Ok(Request::Join(argument.to_string())),
+            "GUESS" => input::parse_guess(argument).map(Request::Guess).map_err(|e| e.to_string()),
+            "LEADERBOARD" => Ok(Request::Leaderboard),
+            "QUIT" => Ok(Request::Quit),
+            _ => Err(format!("unknown command '{}'", keyword)),
+        }
+    }
+}
+
+/// A line sent by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Welcome { name: String, round: u32, min: u32, max: u32 },
+    Small,
+    Big,
+    Win,
+    Lost,
+    OutOfRange { min: u32, max: u32 },
+    Winner { name: String, secret: u32 },
+    /// Everybody ran out of attempts.
+    NoWinner { secret: u32 },
+    Round { round: u32, min: u32, max: u32 },
+    /// Players and the rounds they won, most wins first.
+    Leaderboard(Vec<(String, u32)>),
+    Bye,
+    Error(String),
+}
+
+impl fmt::Display for Message {
+    /// The message as it goes over the wire, without the line ending.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Message::Welcome { ref name, round, min, max } => {
+                write!(f, "WELCOME {} {} {} {}", name, round, min, max)
+            }
+            Message::Small => write!(f, "SMALL"),
+            Message::Big => write!(f, "BIG"),
+            Message::Win => write!(f, "WIN"),
+            Message::Lost => write!(f, "LOST"),
+            Message::OutOfRange { min, max } => write!(f, "RANGE {} {}", min, max),
+            Message::Winner { ref name, secret } => write!(f, "WINNER {} {}", name, secret),
+            Message::NoWinner { secret } => write!(f, "NOWINNER {}", secret),
+            Message::Round { round, min, max } => write!(f, "ROUND {} {} {}", round, min, max),
+            Message::Leaderboard(ref wins) => {
+                write!(f, "LEADERBOARD")?;
+                for &(ref name, wins) in wins {
+                    write!(f, " {}={}", name, wins)?;
+                }
+                Ok(())
+            }
+            Message::Bye => write!(f, "BYE"),
+            Message::Error(ref text) => write!(f, "ERROR {}", text),
+        }
+    }
+}
+
+/// Messages to send, and who to send them to.
+pub type Outbox = Vec<(ClientId, Message)>;
+
+/// A connected client. It gets a `Game` once it has joined.
+struct Player {
+    name: String,
+    game: Game,
+}
+
+/// The shared game, without any networking.
+pub struct Lobby<S> {
+    rules: Rules,
+    source: S,
+    round: u32,
+    secret: u32,
+    players: BTreeMap<ClientId, Player>,
+    /// Names that ran out of attempts this round, joined or not.
+    lost: HashSet<String>,
+    wins: HashMap<String, u32>,
+}
+
+impl<S: SecretSource> Lobby<S> {
+    /// Opens the lobby with the first round's secret drawn from `source`.
+    pub fn new(rules: Rules, mut source: S) -> Result<Lobby<S>, SecretError> {
+        let secret = source.next_secret(&rules)?;
+        Ok(Lobby {
+            rules,
+            source,
+            round: 1,
+            secret,
+            players: BTreeMap::new(),
+            lost: HashSet::new(),
+            wins: HashMap::new(),
+        })
+    }
+
+    /// The number of the current round, starting at 1.
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    /// Handles one line from `client` and returns what to send to whom.
+    pub fn handle(&mut self, client: ClientId, line: &str) -> Outbox {
+        let request = match Request::parse(line) {
+            Ok(request) => request,
+            Err(text) => return vec![(client, Message::Error(text))],
+        };
+
+        match request {
+            Request::Join(name) => self.join(client, name),
+            Request::Guess(guess) => self.guess(client, guess),
+            Request::Leaderboard => vec![(client, Message::Leaderboard(self.leaderboard()))],
+            Request::Quit => {
+                let mut outbox = self.leave(client);
+                outbox.push((client, Message::Bye));
+                outbox
+            }
+        }
+    }
+
+    /// Forgets a client that went away. If everybody left behind has
+    /// lost, that ends the round.
+    pub fn leave(&mut self, client: ClientId) -> Outbox {
+        if self.players.remove(&client).is_some() && self.everybody_lost() {
+            return self.nobody_won();
+        }
+        Vec::new()
+    }
+
+    /// Everyone who won a round, most wins first, then by name.
+    pub fn leaderboard(&self) -> Vec<(String, u32)> {
+        let mut wins: Vec<(String, u32)> =
+            self.wins.iter().map(|(name, &wins)| (name.clone(), wins)).collect();
+        wins.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        wins
+    }
+
+    fn join(&mut self, client: ClientId, name: String) -> Outbox {
+        if self.players.contains_key(&client) {
+            return vec![(client, Message::Error("already joined".to_string()))];
+        }
+        if self.players.values().any(|p| p.name == name) {
+            return vec![(client, Message::Error(format!("the name {} is taken", name)))];
+        }
+
+        let welcome = Message::Welcome {
+            name: name.clone(),
+            round: self.round,
+            min: self.rules.min,
+            max: self.rules.max,
+        };
+        self.players.insert(client, Player {
+            name,
+            game: Game::with_rules(self.secret, self.rules),
+        });
+        let mut outbox = vec![(client, welcome)];
+        // Somebody who lost, left and came back can't start over, but
+        // alone they don't have to wait for a round nobody plays.
+        if self.everybody_lost() {
+            outbox.extend(self.nobody_won());
+        }
+        outbox
+    }
+
+    fn guess(&mut self, client: ClientId, guess: u32) -> Outbox {
+        let player = match self.players.get_mut(&client) {
+            Some(player) => player,
+            None => return vec![(client, Message::Error("JOIN first".to_string()))],
+        };
+        if self.lost.contains(&player.name) {
+            return vec![(client, Message::Error("no attempts left, wait for the next round"
+                                                .to_string()))];
+        }
+
+        let reply = match player.game.guess(guess) {
+            Feedback::TooSmall => Message::Small,
+            Feedback::TooBig => Message::Big,
+            Feedback::OutOfRange => Message::OutOfRange { min: self.rules.min, max: self.rules.max },
+            Feedback::Correct => {
+                let name = player.name.clone();
+                return self.win(client, name);
+            }
+        };
+
+        if player.game.status() == Status::Lost {
+            self.lost.insert(player.name.clone());
+            let mut outbox = vec![(client, Message::Lost)];
+            if self.everybody_lost() {
+                outbox.extend(self.nobody_won());
+            }
+            return outbox;
+        }
+        vec![(client, reply)]
+    }
+
+    /// Whether nobody playing can still win the round.
+    fn everybody_lost(&self) -> bool {
+        !self.players.is_empty() && self.players.values().all(|p| self.lost.contains(&p.name))
+    }
+
+    /// `client` found the secret: tell everyone and start the next round.
+    fn win(&mut self, client: ClientId, name: String) -> Outbox {
+        let mut outbox = vec![(client, Message::Win)];
+        *self.wins.entry(name.clone()).or_insert(0) += 1;
+
+        for &other in self.players.keys() {
+            if other != client {
+                outbox.push((other, Message::Winner { name: name.clone(), secret: self.secret }));
+            }
+        }
+        self.next_round(outbox)
+    }
+
+    /// Everybody lost: tell them the secret and start the next round.
+    fn nobody_won(&mut self) -> Outbox {
+        let outbox = self.players.keys()
+            .map(|&id| (id, Message::NoWinner { secret: self.secret }))
+            .collect();
+        self.next_round(outbox)
+    }
+
+    /// Draws a new secret and gives everyone a new game, adding the news
+    /// to `outbox`.
+    fn next_round(&mut self, mut outbox: Outbox) -> Outbox {
+        // If the source runs dry the secret stays the same. Not much of a
+        // game, but better than throwing everybody out.
+        match self.source.next_secret(&self.rules) {
+            Ok(secret) => self.secret = secret,
+            Err(err) => {
+                for &id in self.players.keys() {
+                    outbox.push((id, Message::Error(format!("keeping the old secret: {}", err))));
+                }
+            }
+        }
+        self.round += 1;
+        self.lost.clear();
+
+        for (&id, player) in &mut self.players {
+            player.game = Game::with_rules(self.secret, self.rules);
+            outbox.push((id, Message::Round {
+                round: self.round,
+                min: self.rules.min,
+                max: self.rules.max,
+            }));
+        }
+        outbox
+    }
+}
+
+/// The lobby and a way to reach every connected client.
+struct Hub<S> {
+    lobby: Lobby<S>,
+    clients: HashMap<ClientId, Sender<Message>>,
+}
+
+impl<S> Hub<S> {
+    fn deliver(&self, outbox: Outbox) {
+        for (id, message) in outbox {
+            if let Some(client) = self.clients.get(&id) {
+                // A client that hung up is cleaned up by its own thread.
+                let _ = client.send(message);
+            }
+        }
+    }
+}
+
+/// Accepts clients on `listener` forever, one thread each.
+///
+/// Every client also gets a writer thread that sends it whatever lands in
+/// its channel, so a round won on one connection can be announced on all
+/// the others.
+pub fn serve<S>(listener: TcpListener, lobby: Lobby<S>) -> io::Result<()>
+    where S: SecretSource + Send + 'static
+{
+    let hub = Arc::new(Mutex::new(Hub { lobby, clients: HashMap::new() }));
+
+    for (id, stream) in listener.incoming().enumerate() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            // One failed connection shouldn't stop the server.
+            Err(_) => continue,
+        };
+        let hub = hub.clone();
+        thread::spawn(move || {
+            let _ = handle_client(id, stream, hub);
+        });
+    }
+    Ok(())
+}
+
+fn handle_client<S>(id: ClientId, stream: TcpStream, hub: Arc<Mutex<Hub<S>>>) -> io::Result<()>
+    where S: SecretSource
+{
+    let (tx, rx) = mpsc::channel();
+    let mut writer = stream.try_clone()?;
+    let writer_thread = thread::spawn(move || {
+        for message in rx {
+            if writeln!(writer, "{}", message).is_err() {
+                break;
+            }
+        }
+    });
+
+    hub.lock().unwrap().clients.insert(id, tx);
+
+    let mut result = Ok(());
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                result = Err(err);
+                break;
+            }
+        };
+        let mut hub = hub.lock().unwrap();
+        let outbox = hub.lobby.handle(id, &line);
+        let quit = outbox.iter().any(|&(to, ref message)| to == id && *message == Message::Bye);
+        hub.deliver(outbox);
+        if quit {
+            break;
+        }
+    }
+
+    // Dropping the sender ends the writer thread once it has sent
+    // everything still queued.
+    {
+        let mut hub = hub.lock().unwrap();
+        hub.clients.remove(&id);
+        let outbox = hub.lobby.leave(id);
+        hub.deliver(outbox);
+    }
+    let _ = writer_thread.join();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secret::ListSource;
+
+    fn lobby(secrets: Vec<u32>, max_attempts: Option<u32>) -> Lobby<ListSource> {
+        let rules = Rules { min: 1, max: 10, max_attempts };
+        Lobby::new(rules, ListSource::new(secrets).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parses_requests() {
+        assert_eq!(Ok(Request::Join("ada".to_string())), Request::parse("join  ada\n"));
+        assert_eq!(Ok(Request::Guess(7)), Request::parse("GUESS 7"));
+        assert_eq!(Ok(Request::Leaderboard), Request::parse("leaderboard"));
+        assert_eq!(Ok(Request::Quit), Request::parse("QUIT"));
+        assert!(Request::parse("JOIN").is_err());
+        assert!(Request::parse("JOIN a b").is_err());
+        assert_eq!(Err("'x' is not a number.".to_string()), Request::parse("GUESS x"));
+        assert!(Request::parse("DANCE").is_err());
+    }
+
+    #[test]
+    fn wire_format() {
+        assert_eq!("WELCOME ada 1 1 10",
+                   Message::Welcome { name: "ada".to_string(), round: 1, min: 1, max: 10 }
+                   .to_string());
+        assert_eq!("LEADERBOARD ada=2 bob=1",
+                   Message::Leaderboard(vec![("ada".to_string(), 2), ("bob".to_string(), 1)])
+                   .to_string());
+        assert_eq!("ERROR oops", Message::Error("oops".to_string()).to_string());
+    }
+
+    #[test]
+    fn must_join_before_guessing() {
+        let mut lobby = lobby(vec![5], None);
+        assert_eq!(vec![(0, Message::Error("JOIN first".to_string()))], lobby.handle(0, "GUESS 5"));
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let mut lobby = lobby(vec![5], None);
+        lobby.handle(0, "JOIN ada");
+        match lobby.handle(1, "JOIN ada")[0] {
+            (1, Message::Error(_)) => {}
+            ref other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn first_correct_guess_wins_round_for_everyone() {
+        let mut lobby = lobby(vec![5, 8], None);
+        lobby.handle(0, "JOIN ada");
+        lobby.handle(1, "JOIN bob");
+
+        assert_eq!(vec![(1, Message::Small)], lobby.handle(1, "GUESS 3"));
+        assert_eq!(vec![(0, Message::Big)], lobby.handle(0, "GUESS 9"));
+
+        let round = Message::Round { round: 2, min: 1, max: 10 };
+        assert_eq!(vec![
+            (0, Message::Win),
+            (1, Message::Winner { name: "ada".to_string(), secret: 5 }),
+            (0, round.clone()),
+            (1, round),
+        ], lobby.handle(0, "GUESS 5"));
+
+        // The next round has a new secret.
+        assert_eq!(2, lobby.round());
+        assert_eq!(vec![(1, Message::Big)], lobby.handle(1, "GUESS 9"));
+        assert_eq!(Message::Win, lobby.handle(1, "GUESS 8")[0].1);
+
+        assert_eq!(vec![(0, Message::Leaderboard(vec![("ada".to_string(), 1),
+                                                       ("bob".to_string(), 1)]))],
+                   lobby.handle(0, "LEADERBOARD"));
+    }
+
+    #[test]
+    fn running_out_of_attempts_loses_the_round() {
+        let mut lobby = lobby(vec![5, 6], Some(1));
+        lobby.handle(0, "JOIN ada");
+        lobby.handle(1, "JOIN bob");
+
+        assert_eq!(vec![(0, Message::Lost)], lobby.handle(0, "GUESS 1"));
+        match lobby.handle(0, "GUESS 5")[0] {
+            (0, Message::Error(_)) => {}
+            ref other => panic!("unexpected {:?}", other),
+        }
+
+        // Bob wins, and ada gets a new game in the next round.
+        lobby.handle(1, "GUESS 5");
+        assert_eq!(vec![(0, Message::Win)], lobby.handle(0, "GUESS 6")[..1].to_vec());
+    }
+
+    #[test]
+    fn losers_stay_out_of_the_round_when_they_rejoin() {
+        let mut lobby = lobby(vec![5, 6], Some(1));
+        lobby.handle(0, "JOIN ada");
+        lobby.handle(1, "JOIN bob");
+        assert_eq!(vec![(0, Message::Lost)], lobby.handle(0, "GUESS 1"));
+
+        assert_eq!(vec![(0, Message::Bye)], lobby.handle(0, "QUIT"));
+        assert_eq!(Message::Welcome { name: "ada".to_string(), round: 1, min: 1, max: 10 },
+                   lobby.handle(2, "JOIN ada")[0].1);
+        match lobby.handle(2, "GUESS 5")[0] {
+            (2, Message::Error(_)) => {}
+            ref other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(1, lobby.round());
+    }
+
+    #[test]
+    fn the_round_ends_when_everybody_lost() {
+        let mut lobby = lobby(vec![5, 6], Some(1));
+        lobby.handle(0, "JOIN ada");
+        lobby.handle(1, "JOIN bob");
+        assert_eq!(vec![(0, Message::Lost)], lobby.handle(0, "GUESS 1"));
+
+        let round = Message::Round { round: 2, min: 1, max: 10 };
+        assert_eq!(vec![
+            (1, Message::Lost),
+            (0, Message::NoWinner { secret: 5 }),
+            (1, Message::NoWinner { secret: 5 }),
+            (0, round.clone()),
+            (1, round),
+        ], lobby.handle(1, "GUESS 2"));
+        assert_eq!(Message::Win, lobby.handle(0, "GUESS 6")[0].1);
+        assert_eq!(vec![("ada".to_string(), 1)], lobby.leaderboard());
+    }
+
+    #[test]
+    fn the_round_ends_when_the_last_player_in_it_leaves() {
+        let mut lobby = lobby(vec![5, 6], Some(1));
+        lobby.handle(0, "JOIN ada");
+        lobby.handle(1, "JOIN bob");
+        lobby.handle(0, "GUESS 1");
+
+        assert_eq!(vec![
+            (0, Message::NoWinner { secret: 5 }),
+            (0, Message::Round { round: 2, min: 1, max: 10 }),
+            (1, Message::Bye),
+        ], lobby.handle(1, "QUIT"));
+        assert_eq!(Message::Win, lobby.handle(0, "GUESS 6")[0].1);
+    }
+
+    #[test]
+    fn out_of_range_guesses() {
+        let mut lobby = lobby(vec![5], Some(1));
+        lobby.handle(0, "JOIN ada");
+        assert_eq!(vec![(0, Message::OutOfRange { min: 1, max: 10 })],
+                   lobby.handle(0, "GUESS 11"));
+    }
+}
@@ -0,0 +1,316 @@
+//! Computer players for the guessing game.
+//!
+//! Each `Strategy` only sees what a human would see: the rules, its own
+//! guesses and the feedback. `play` lets a strategy loose on a `Game`.
+
+use rand::Rng;
+
+use game::{Feedback, Game, Rules, Status};
+
+/// A way of picking guesses.
+pub trait Strategy {
+    /// Short name used in reports.
+    fn name(&self) -> &str;
+
+    /// Forgets everything and gets ready for a new game played with `rules`.
+    fn reset(&mut self, rules: &Rules);
+
+    /// The next number to guess.
+    fn next_guess(&mut self) -> u32;
+
+    /// Learns what the game answered to `guess`.
+    fn feedback(&mut self, guess: u32, feedback: Feedback);
+}
+
+/// The numbers that can still be the secret, bounds included.
+///
+/// Every strategy below keeps one of these, they differ only in what
+/// they guess from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub low: u32,
+    pub high: u32,
+}
+
+impl Interval {
+    /// Everything the rules allow.
+    pub fn new(rules: &Rules) -> Interval {
+        Interval { low: rules.min, high: rules.max }
+    }
+
+    /// The number in the middle, rounded down.
+    pub fn middle(&self) -> u32 {
+        self.low + (self.high - self.low) / 2
+    }
+
+    /// Whether no number is left. Only happens when feedback contradicts
+    /// itself.
+    pub fn is_empty(&self) -> bool {
+        self.low > self.high
+    }
+
+    /// Narrows the interval with what the game said about `guess`.
+    pub fn narrow(&mut self, guess: u32, feedback: Feedback) {
+        match feedback {
+            // The secret is bigger than the guess.
+            Feedback::TooSmall => self.low = self.low.max(guess.saturating_add(1)),
+            // The secret is smaller than the guess. Going below zero
+            // empties the interval instead of wrapping around.
+            Feedback::TooBig => match guess.checked_sub(1) {
+                Some(below) => self.high = self.high.min(below),
+                None => {
+                    self.low = 1;
+                    self.high = 0;
+                }
+            },
+            Feedback::Correct => {
+                self.low = guess;
+                self.high = guess;
+            }
+            Feedback::OutOfRange => {}
+        }
+    }
+}
+
+/// Always guesses the middle of what is left. Needs at most
+/// ⌈log2(n + 1)⌉ guesses for a range of n numbers.
+pub struct BinarySearch {
+    interval: Interval,
+}
+
+impl BinarySearch {
+    pub fn new() -> BinarySearch {
+        BinarySearch { interval: Interval::new(&Rules::default()) }
+    }
+}
+
+impl Default for BinarySearch {
+    fn default() -> BinarySearch {
+        BinarySearch::new()
+    }
+}
+
+impl Strategy for BinarySearch {
+    fn name(&self) -> &str {
+        "binary"
+    }
+
+    fn reset(&mut self, rules: &Rules) {
+        self.interval = Interval::new(rules);
+    }
+
+    fn next_guess(&mut self) -> u32 {
+        self.interval.middle()
+    }
+
+    fn feedback(&mut self, guess: u32, feedback: Feedback) {
+        self.interval.narrow(guess, feedback);
+    }
+}
+
+/// Guesses any number that can still be the secret, at random.
+pub struct RandomGuess<R> {
+    interval: Interval,
+    rng: R,
+}
+
+impl<R: Rng> RandomGuess<R> {
+    pub fn new(rng: R) -> RandomGuess<R> {
+        RandomGuess { interval: Interval::new(&Rules::default()), rng }
+    }
+}
+
+impl<R: Rng> Strategy for RandomGuess<R> {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn reset(&mut self, rules: &Rules) {
+        self.interval = Interval::new(rules);
+    }
+
+    fn next_guess(&mut self) -> u32 {
+        // gen_range excludes its upper bound. Rules never allow
+        // u32::MAX, so high + 1 can't overflow.
+        self.rng.gen_range(self.interval.low, self.interval.high + 1)
+    }
+
+    fn feedback(&mut self, guess: u32, feedback: Feedback) {
+        self.interval.narrow(guess, feedback);
+    }
+}
+
+/// Tries every number from the bottom up, ignoring the hints.
+pub struct LinearScan {
+    next: u32,
+}
+
+impl LinearScan {
+    pub fn new() -> LinearScan {
+        LinearScan { next: Rules::default().min }
+    }
+}
+
+impl Default for LinearScan {
+    fn default() -> LinearScan {
+        LinearScan::new()
+    }
+}
+
+impl Strategy for LinearScan {
+    fn name(&self) -> &str {
+        "linear"
+    }
+
+    fn reset(&mut self, rules: &Rules) {
+        self.next = rules.min;
+    }
+
+    fn next_guess(&mut self) -> u32 {
+        self.next
+    }
+
+    fn feedback(&mut self, guess: u32, _feedback: Feedback) {
+        self.next = guess.saturating_add(1);
+    }
+}
+
+/// A deliberately bad binary search that reads every hint backwards.
+///
+/// On "too small" it goes looking below the guess. The side it searches
+/// never holds the secret, so sooner or later it runs out of numbers
+/// there. It then admits the mistake, goes back to the numbers the
+/// hints really allow, and makes the same mistake again. It does find
+/// the secret, in about log2(n)² guesses.
+pub struct BisectWrongSide {
+    /// What the hints really allow.
+    truth: Interval,
+    /// Where it is looking.
+    believed: Interval,
+}
+
+impl BisectWrongSide {
+    pub fn new() -> BisectWrongSide {
+        let interval = Interval::new(&Rules::default());
+        BisectWrongSide { truth: interval, believed: interval }
+    }
+}
+
+impl Default for BisectWrongSide {
+    fn default() -> BisectWrongSide {
+        BisectWrongSide::new()
+    }
+}
+
+impl Strategy for BisectWrongSide {
+    fn name(&self) -> &str {
+        "wrong-side"
+    }
+
+    fn reset(&mut self, rules: &Rules) {
+        self.truth = Interval::new(rules);
+        self.believed = self.truth;
+    }
+
+    fn next_guess(&mut self) -> u32 {
+        self.believed.middle()
+    }
+
+    fn feedback(&mut self, guess: u32, feedback: Feedback) {
+        self.truth.narrow(guess, feedback);
+
+        let backwards = match feedback {
+            Feedback::TooSmall => Feedback::TooBig,
+            Feedback::TooBig => Feedback::TooSmall,
+            other => other,
+        };
+        self.believed.narrow(guess, backwards);
+
+        if self.believed.is_empty() {
+            self.believed = self.truth;
+        }
+    }
+}
+
+/// Plays `game` to the end with `strategy` and returns how it ended.
+///
+/// # Panics
+/// Panics if the strategy makes more than `u32::MAX` guesses without
+/// finishing, which only a broken strategy on an unlimited game can do.
+pub fn play<S: Strategy + ?Sized>(strategy: &mut S, game: &mut Game) -> Status {
+    strategy.reset(game.rules());
+
+    while !game.is_finished() {
+        assert!(game.attempts() < u32::MAX, "{} never finds the secret", strategy.name());
+        let guess = strategy.next_guess();
+        let feedback = game.guess(guess);
+        strategy.feedback(guess, feedback);
+    }
+
+    game.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secret::seeded_rng;
+
+    fn every_secret<S: Strategy>(strategy: &mut S, rules: Rules) -> Vec<u32> {
+        (rules.min..rules.max + 1).map(|secret| {
+            let mut game = Game::with_rules(secret, rules);
+            assert_eq!(Status::Won, play(strategy, &mut game));
+            game.attempts()
+        }).collect()
+    }
+
+    #[test]
+    fn binary_search_is_logarithmic() {
+        let rules = Rules { min: 1, max: 100, max_attempts: None };
+        let attempts = every_secret(&mut BinarySearch::new(), rules);
+        assert_eq!(7, *attempts.iter().max().unwrap());
+
+        // 127 = 2^7 - 1 numbers need 7 guesses, one more needs 8.
+        let rules = Rules { min: 0, max: 126, max_attempts: None };
+        assert_eq!(7, *every_secret(&mut BinarySearch::new(), rules).iter().max().unwrap());
+        let rules = Rules { min: 0, max: 127, max_attempts: None };
+        assert_eq!(8, *every_secret(&mut BinarySearch::new(), rules).iter().max().unwrap());
+    }
+
+    #[test]
+    fn linear_scan_needs_secret_minus_min_plus_one() {
+        let rules = Rules { min: 10, max: 20, max_attempts: None };
+        let attempts = every_secret(&mut LinearScan::new(), rules);
+        assert_eq!((1..12).collect::<Vec<u32>>(), attempts);
+    }
+
+    #[test]
+    fn random_and_wrong_side_always_win() {
+        let rules = Rules { min: 1, max: 100, max_attempts: None };
+        every_secret(&mut RandomGuess::new(seeded_rng(3)), rules);
+        let wrong_side = every_secret(&mut BisectWrongSide::new(), rules);
+        let binary = every_secret(&mut BinarySearch::new(), rules);
+        assert!(wrong_side.iter().sum::<u32>() > binary.iter().sum::<u32>());
+    }
+
+    #[test]
+    fn strategies_can_lose() {
+        let rules = Rules { min: 1, max: 100, max_attempts: Some(3) };
+        let mut game = Game::with_rules(100, rules);
+        assert_eq!(Status::Lost, play(&mut LinearScan::new(), &mut game));
+    }
+
+    #[test]
+    fn interval_narrowing() {
+        let mut interval = Interval { low: 0, high: 10 };
+        interval.narrow(3, Feedback::TooSmall);
+        assert_eq!(Interval { low: 4, high: 10 }, interval);
+        interval.narrow(8, Feedback::TooBig);
+        assert_eq!(Interval { low: 4, high: 7 }, interval);
+        interval.narrow(2, Feedback::TooBig);
+        assert!(interval.is_empty());
+
+        let mut interval = Interval { low: 0, high: 10 };
+        interval.narrow(0, Feedback::TooBig);
+        assert!(interval.is_empty());
+    }
+}
@@ -0,0 +1,353 @@
+//! Time attack: find the secret before the clock runs out.
+//!
+//! Reading a line blocks, so the game loop can't watch the clock while it
+//! waits for the player. Instead two threads feed one channel: a reader
+//! thread sends each line as it is typed, and a timer thread sends a tick
+//! every second and a timeout at the end. `Clocked` hands `puzzle::play`
+//! whichever comes first, so the game ends on time even while the reader
+//! is still stuck in `read_line`.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use game::Status;
+use hints::GUESS_PENALTY;
+use i18n::Catalog;
+use input::{self, InputError};
+use puzzle::{self, Lines, Next, Puzzle};
+
+/// Seconds per game when `--seconds` isn't given.
+pub const DEFAULT_SECONDS: u64 = 30;
+
+/// The most seconds `--seconds` allows, an hour.
+pub const MAX_SECONDS: u64 = 3600;
+
+/// Bonus points for every whole second left on the clock.
+pub const POINTS_PER_SECOND: u32 = 5;
+
+/// What the game loop is waiting for.
+#[derive(Debug)]
+pub enum Event {
+    /// The player typed a line, or reading failed.
+    Line(Result<String, InputError>),
+    /// Another second went by. Carries the time left.
+    Tick(Duration),
+    /// The time is up.
+    Timeout,
+}
+
+/// Sends a `Tick` every `every` and a `Timeout` at `deadline`. Stops
+/// early when nobody listens anymore.
+pub fn spawn_timer(deadline: Instant, every: Duration, events: Sender<Event>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left == Duration::from_secs(0) {
+                let _ = events.send(Event::Timeout);
+                return;
+            }
+            thread::sleep(left.min(every));
+
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left > Duration::from_secs(0) && events.send(Event::Tick(left)).is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// Sends every line of `input` until it closes or fails, or nobody
+/// listens anymore.
+pub fn spawn_reader<R>(mut input: R, events: Sender<Event>) -> JoinHandle<()>
+    where R: BufRead + Send + 'static
+{
+    thread::spawn(move || {
+        loop {
+            let line = input::read_line(&mut input);
+            let stop = line.is_err();
+            if events.send(Event::Line(line)).is_err() || stop {
+                return;
+            }
+        }
+    })
+}
+
+/// How a timed game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// `Status::Lost` if the attempts or the time ran out.
+    pub status: Status,
+    /// Time left on the clock. Zero after a timeout.
+    pub time_left: Duration,
+    /// Whether the clock ran out.
+    pub timed_out: bool,
+}
+
+/// Whether a tick with `left` on the clock is worth telling the player
+/// about: every ten seconds, and each of the last five.
+fn announce(left: Duration) -> bool {
+    let secs = left.as_secs();
+    secs > 0 && (secs <= 5 || secs.is_multiple_of(10))
+}
+
+/// The player's lines as they arrive on `events`, between the ticks of
+/// the clock.
+pub struct Clocked<'a> {
+    events: &'a Receiver<Event>,
+    deadline: Instant,
+    timed_out: bool,
+}
+
+impl<'a> Clocked<'a> {
+    pub fn new(events: &'a Receiver<Event>, deadline: Instant) -> Clocked<'a> {
+        Clocked { events, deadline, timed_out: false }
+    }
+}
+
+impl<'a> Lines for Clocked<'a> {
+    fn started<W: Write>(&mut self, catalog: &Catalog, output: &mut W) -> Result<(), InputError> {
+        // Rounded up, so a clock set a moment ago to 30 seconds says 30.
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        let seconds = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+        writeln!(output, "{}", catalog.format("time_budget", &[("seconds", &seconds)]))?;
+        Ok(())
+    }
+
+    fn next<W: Write>(&mut self, catalog: &Catalog, output: &mut W) -> Result<Next, InputError> {
+        loop {
+            // Both senders gone means neither input nor clock is left,
+            // which is the same as the input closing.
+            match self.events.recv() {
+                Ok(Event::Line(line)) => return line.map(Next::Line),
+                Ok(Event::Tick(left)) => {
+                    if announce(left) {
+                        writeln!(output, "{}", catalog.format("time_left", &[("seconds", &left.as_secs())]))?;
+                    }
+                }
+                Ok(Event::Timeout) => {
+                    self.timed_out = true;
+                    return Ok(Next::TimeUp);
+                }
+                Err(_) => return Err(InputError::Eof),
+            }
+        }
+    }
+}
+
+/// Plays `puzzle` against a clock that runs out at `deadline`, the same
+/// one `spawn_timer` was given. Lines, ticks and the timeout all arrive
+/// on `events`; the threads of `spawn_timer` and `spawn_reader` send them
+/// in a real game.
+///
+/// # Failures
+/// Returns the error of the reader, such as `InputError::Eof`, if the
+/// input ends before the game does, and `InputError::Io` if writing fails.
+pub fn play<P, W>(puzzle: &mut P, deadline: Instant, events: &Receiver<Event>, output: &mut W)
+    -> Result<Outcome, InputError>
+    where P: Puzzle + ?Sized, W: Write
+{
+    let mut lines = Clocked::new(events, deadline);
+    let status = puzzle::play(puzzle, &mut lines, output)?;
+
+    Ok(Outcome {
+        status,
+        time_left: if lines.timed_out { Duration::from_secs(0) }
+                   else { deadline.saturating_duration_since(Instant::now()) },
+        timed_out: lines.timed_out,
+    })
+}
+
+/// How a timed game's points add up: fewer guesses and more time left
+/// both score higher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeScore {
+    /// Points the game started with.
+    pub base: u32,
+    /// Guesses after the first, `GUESS_PENALTY` each.
+    pub extra_guesses: u32,
+    /// Whole seconds left, `POINTS_PER_SECOND` each.
+    pub seconds_left: u32,
+    pub won: bool,
+}
+
+impl TimeScore {
+    pub fn new(base: u32, attempts: u32, outcome: &Outcome) -> TimeScore {
+        TimeScore {
+            base,
+            extra_guesses: attempts.saturating_sub(1),
+            seconds_left: u32::try_from(outcome.time_left.as_secs()).unwrap_or(u32::MAX),
+            won: outcome.status == Status::Won,
+        }
+    }
+
+    /// The final score. Never below zero, and zero for a lost game.
+    pub fn total(&self) -> u32 {
+        if !self.won {
+            return 0;
+        }
+        self.base
+            .saturating_sub(self.extra_guesses.saturating_mul(GUESS_PENALTY))
+            .saturating_add(self.seconds_left.saturating_mul(POINTS_PER_SECOND))
+    }
+}
+
+impl fmt::Display for TimeScore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::english().time_score(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::{Game, Rules};
+    use hints::Hints;
+    use puzzle::NumberPuzzle;
+    use std::io::Cursor;
+    use std::sync::mpsc;
+
+    fn number() -> NumberPuzzle {
+        let rules = Rules { min: 1, max: 100, max_attempts: Some(3) };
+        NumberPuzzle::new(Game::with_rules(42, rules), Hints::new(&[]))
+    }
+
+    /// Plays the scripted `events` with a long budget, so the real clock
+    /// never matters.
+    fn run(events: Vec<Event>) -> (Result<Outcome, InputError>, String) {
+        let (sender, receiver) = mpsc::channel();
+        for event in events {
+            sender.send(event).unwrap();
+        }
+        drop(sender);
+
+        let mut output = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let outcome = play(&mut number(), deadline, &receiver, &mut output);
+        (outcome, String::from_utf8(output).unwrap())
+    }
+
+    fn line(text: &str) -> Event {
+        Event::Line(Ok(text.to_string()))
+    }
+
+    #[test]
+    fn win_in_time() {
+        let (outcome, output) = run(vec![
+            line("50\n"),
+            Event::Tick(Duration::from_millis(59_500)),
+            Event::Tick(Duration::from_millis(50_200)),
+            line("42\n"),
+        ]);
+        let outcome = outcome.unwrap();
+        assert_eq!(Status::Won, outcome.status);
+        assert!(!outcome.timed_out);
+        assert!(outcome.time_left > Duration::from_secs(50));
+        assert_eq!("\
+Guess the number!
+It is between 1 and 100.
+You have 60 second(s).
+Please input your guess.
+You guessed: 50
+Too big!
+2 attempt(s) left.
+Please input your guess.
+50 second(s) left.
+You guessed: 42
+You win!
+", output);
+    }
+
+    #[test]
+    fn timeout_while_typing() {
+        let (outcome, output) = run(vec![line("abc\n"), Event::Timeout, line("42\n")]);
+        let outcome = outcome.unwrap();
+        assert_eq!(Outcome { status: Status::Lost, time_left: Duration::from_secs(0), timed_out: true },
+                   outcome);
+        assert!(output.ends_with("\
+'abc' is not a number.
+Please input your guess.
+Time's up! The secret number was 42.
+"), "{}", output);
+    }
+
+    #[test]
+    fn out_of_attempts_and_eof() {
+        let (outcome, output) = run(vec![line("1\n"), line("2\n"), line("3\n")]);
+        assert_eq!(Status::Lost, outcome.unwrap().status);
+        assert!(output.ends_with("You lose! The secret number was 42.\n"));
+
+        match run(vec![line("1\n"), Event::Line(Err(InputError::Eof))]).0 {
+            Err(InputError::Eof) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn announcements() {
+        let secs = Duration::from_secs;
+        assert!(announce(secs(30)));
+        assert!(!announce(secs(29)));
+        assert!(announce(secs(5)));
+        assert!(announce(secs(1)));
+        assert!(!announce(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn timer_thread() {
+        let (sender, receiver) = mpsc::channel();
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let timer = spawn_timer(deadline, Duration::from_millis(10), sender);
+
+        let events: Vec<Event> = receiver.iter().collect();
+        timer.join().unwrap();
+
+        match events.last() {
+            Some(&Event::Timeout) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        let ticks: Vec<Duration> = events.iter()
+            .filter_map(|e| match *e { Event::Tick(left) => Some(left), _ => None })
+            .collect();
+        assert!(!ticks.is_empty());
+        assert!(ticks.windows(2).all(|w| w[0] > w[1]), "{:?}", ticks);
+    }
+
+    #[test]
+    fn reader_thread() {
+        let (sender, receiver) = mpsc::channel();
+        spawn_reader(Cursor::new("1\n2\n"), sender).join().unwrap();
+
+        let lines: Vec<String> = receiver.iter()
+            .map(|e| match e {
+                Event::Line(Ok(line)) => line,
+                Event::Line(Err(err)) => err.to_string(),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(lines, ["1\n", "2\n", "No more input, quitting."]);
+    }
+
+    #[test]
+    fn scores() {
+        let outcome = Outcome { status: Status::Won, time_left: Duration::from_millis(9_900),
+                                timed_out: false };
+        let score = TimeScore::new(100, 3, &outcome);
+        assert_eq!(125, score.total());
+        assert_eq!("Score: 100 - 20 for 2 extra guess(es) + 45 for 9 second(s) left = 125 points.",
+                   score.to_string());
+
+        let lost = Outcome { status: Status::Lost, time_left: Duration::from_secs(0), timed_out: true };
+        assert_eq!(0, TimeScore::new(100, 1, &lost).total());
+
+        // Far more time than fits in the points.
+        let forever = Outcome { status: Status::Won, time_left: Duration::from_secs(u64::MAX),
+                                timed_out: false };
+        let score = TimeScore::new(100, 1, &forever);
+        assert_eq!(u32::MAX, score.seconds_left);
+        assert_eq!(u32::MAX, score.total());
+    }
+}
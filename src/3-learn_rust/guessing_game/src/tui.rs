@@ -0,0 +1,359 @@
+//! A full screen front-end for the number game.
+//!
+//! `App` holds the game and what the player is typing, turns key presses
+//! into guesses and hint requests, and draws itself on any ratatui
+//! backend. The binary runs it on the real terminal with crossterm; the
+//! tests draw it on a `TestBackend` and compare the screen text.
+
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use game::{Feedback, Status};
+use i18n::Catalog;
+use puzzle::{NumberPuzzle, Puzzle};
+use solver::Interval;
+
+/// Longest line the player can type.
+const MAX_INPUT: usize = 20;
+
+/// The state of the screen.
+pub struct App {
+    number: NumberPuzzle,
+    /// What the player is typing.
+    input: String,
+    /// The answer to the last line: feedback, a hint or an error.
+    message: String,
+    /// Hints bought so far, as shown when they were bought.
+    clues: Vec<String>,
+    /// The player pressed Esc.
+    quit: bool,
+}
+
+impl App {
+    pub fn new(number: NumberPuzzle) -> App {
+        let message = if number.hints().allowed().is_empty() {
+            String::new()
+        } else {
+            number.catalog().get("hint_intro").to_string()
+        };
+        App { number, input: String::new(), message, clues: Vec::new(), quit: false }
+    }
+
+    pub fn number(&self) -> &NumberPuzzle {
+        &self.number
+    }
+
+    /// Gives the game back, for scoring after the screen is closed.
+    pub fn into_number(self) -> NumberPuzzle {
+        self.number
+    }
+
+    /// Whether the event loop should stop: the player quit, or the game
+    /// is over and they pressed a key after seeing the result.
+    pub fn is_done(&self) -> bool {
+        self.quit
+    }
+
+    /// Handles one key press.
+    pub fn key(&mut self, key: KeyEvent) {
+        // Terminals that report releases would type everything twice.
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        if self.number.game().is_finished() {
+            // Any key leaves the result screen.
+            self.quit = true;
+            return;
+        }
+        match key.code {
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Enter => self.submit(),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) if self.input.len() < MAX_INPUT && !c.is_control() => {
+                self.input.push(c)
+            }
+            _ => {}
+        }
+    }
+
+    fn catalog(&self) -> &Catalog {
+        self.number.catalog()
+    }
+
+    /// Plays the typed line, the same way the line prompt would.
+    fn submit(&mut self) {
+        let line = self.input.split_off(0);
+
+        let used = self.number.hints().used().len();
+        if let Some(text) = self.number.command(&line) {
+            // Only a hint that was bought is a clue; errors and the menu aren't.
+            if self.number.hints().used().len() > used {
+                self.clues.push(text.clone());
+            }
+            self.message = text;
+            return;
+        }
+        let guess = match self.number.parse_guess(&line) {
+            Ok(guess) => guess,
+            Err(message) => {
+                self.message = message;
+                return;
+            }
+        };
+        let feedback = self.number.guess(&guess);
+        self.message = self.number.show(&guess, &feedback).replace('\n', " ");
+
+        if self.number.game().status() == Status::Lost {
+            self.message = self.catalog().format("lose", &[("reveal", &self.number.reveal())]);
+        }
+    }
+
+    /// Numbers the game still allows, going by the guesses so far.
+    fn interval(&self) -> Interval {
+        let game = self.number.game();
+        let mut interval = Interval::new(game.rules());
+        for &guess in game.history() {
+            interval.narrow(guess, Feedback::from(guess.cmp(&game.secret())));
+        }
+        interval
+    }
+
+    /// Draws the whole screen.
+    pub fn draw(&self, frame: &mut Frame) {
+        let [range, attempts, history, hints, message, input] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(self.clues.len() as u16 + 2),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ]).areas(frame.area());
+
+        self.draw_range(frame, range);
+
+        let catalog = self.catalog();
+        let left = match self.number.attempts_left() {
+            Some(left) => catalog.format("tui_attempts_left", &[("left", &left)]),
+            None => catalog.format("tui_no_limit", &[("count", &self.number.game().attempts())]),
+        };
+        frame.render_widget(Paragraph::new(left), attempts);
+
+        self.draw_history(frame, history);
+
+        let clues: Vec<Line> = self.clues.iter().map(|c| Line::from(c.as_str())).collect();
+        let title = format!(" {} ", catalog.get("tui_hints"));
+        frame.render_widget(Paragraph::new(clues).block(Block::bordered().title(title)), hints);
+
+        frame.render_widget(Paragraph::new(self.message.as_str()), message);
+
+        let prompt = if self.number.game().is_finished() {
+            Line::from(catalog.get("tui_quit"))
+        } else {
+            Line::from(vec![
+                Span::styled(format!("{} ", catalog.get("tui_guess")),
+                             Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(self.input.as_str()),
+                Span::raw("_"),
+            ])
+        };
+        frame.render_widget(Paragraph::new(prompt), input);
+    }
+
+    /// The range of the game, with the part still possible filled in.
+    fn draw_range(&self, frame: &mut Frame, area: Rect) {
+        let rules = *self.number.game().rules();
+        let interval = self.interval();
+        let catalog = self.catalog();
+        let block = Block::bordered().title(format!(" {} ", catalog.get("tui_title")));
+        let width = block.inner(area).width as u64;
+
+        let text = vec![
+            Line::from(catalog.format("tui_possible", &[
+                ("low", &interval.low), ("high", &interval.high),
+                ("min", &rules.min), ("max", &rules.max),
+            ])),
+            Line::from(Span::styled(bar(rules.min, rules.max, &interval, width),
+                                    Style::default().fg(Color::Green))),
+        ];
+        frame.render_widget(Paragraph::new(text).block(block), area);
+    }
+
+    /// Every guess so far, newest first so the latest is always visible.
+    fn draw_history(&self, frame: &mut Frame, area: Rect) {
+        let game = self.number.game();
+        let catalog = self.catalog();
+        let lines: Vec<Line> = game.history().iter().rev()
+            .map(|&guess| {
+                let verdict = match Feedback::from(guess.cmp(&game.secret())) {
+                    Feedback::TooSmall => catalog.get("tui_too_small"),
+                    Feedback::TooBig => catalog.get("tui_too_big"),
+                    _ => catalog.get("tui_correct"),
+                };
+                Line::from(format!("{:>6}  {}", guess, verdict))
+            })
+            .collect();
+        let title = format!(" {} ", catalog.get("tui_guesses"));
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), area);
+    }
+}
+
+/// A bar `width` cells wide standing for `min..=max`, with the cells that
+/// overlap `interval` filled. At least one cell stays filled while any
+/// number is possible, however wide the range.
+fn bar(min: u32, max: u32, interval: &Interval, width: u64) -> String {
+    let numbers = (max - min) as u64 + 1;
+    let (start, end) = if interval.is_empty() {
+        (0, 0)
+    } else {
+        let start = (interval.low - min) as u64 * width / numbers;
+        let end = ((interval.high - min) as u64 + 1) * width;
+        (start, end.div_ceil(numbers).max(start + 1))
+    };
+    (0..width).map(|cell| if cell >= start && cell < end { '█' } else { '░' }).collect()
+}
+
+/// Runs `app` on the real terminal until the player is done.
+///
+/// # Failures
+/// Returns errors from the terminal.
+pub fn run(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+    while !app.is_done() {
+        terminal.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            app.key(key);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::{Game, Rules};
+    use hints::{Hint, Hints};
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    fn app(allowed: &[Hint]) -> App {
+        let rules = Rules { min: 1, max: 100, max_attempts: Some(5) };
+        App::new(NumberPuzzle::new(Game::with_rules(42, rules), Hints::new(allowed)))
+    }
+
+    fn type_keys(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.key(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    fn type_line(app: &mut App, line: &str) {
+        type_keys(app, line);
+        app.key(KeyEvent::from(KeyCode::Enter));
+    }
+
+    /// The text on a 44 by 16 screen, one string per row.
+    fn screen(app: &App) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(44, 16)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        buffer.content().chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn bar_shrinks() {
+        let full = Interval { low: 1, high: 100 };
+        assert_eq!("██████████", bar(1, 100, &full, 10));
+        assert_eq!("░░░░░█████", bar(1, 100, &Interval { low: 51, high: 100 }, 10));
+        assert_eq!("░░░░█░░░░░", bar(1, 100, &Interval { low: 42, high: 42 }, 10));
+        assert_eq!("░░░░░░░░░░", bar(1, 100, &Interval { low: 2, high: 1 }, 10));
+        assert_eq!("░░░██░", bar(0, 5, &Interval { low: 3, high: 4 }, 6));
+    }
+
+    #[test]
+    fn start_screen() {
+        assert_eq!(screen(&app(&[Hint::Parity])), [
+            "┌ Guess the number ────────────────────────┐",
+            "│Still possible: 1 to 100, of 1 to 100     │",
+            "│██████████████████████████████████████████│",
+            "└──────────────────────────────────────────┘",
+            "5 attempt(s) left                           ",
+            "┌ Guesses ─────────────────────────────────┐",
+            "│                                          │",
+            "│                                          │",
+            "│                                          │",
+            "│                                          │",
+            "│                                          │",
+            "└──────────────────────────────────────────┘",
+            "┌ Hints ───────────────────────────────────┐",
+            "└──────────────────────────────────────────┘",
+            "Type 'hint' to see the hints you can buy.   ",
+            "Guess: _                                    ",
+        ]);
+    }
+
+    #[test]
+    fn guesses_and_hints() {
+        let mut app = app(&[Hint::Parity]);
+        type_line(&mut app, "50");
+        type_line(&mut app, "hint parity");
+        type_keys(&mut app, "2x");
+        app.key(KeyEvent::from(KeyCode::Backspace));
+        type_line(&mut app, "0");
+
+        assert_eq!(screen(&app), [
+            "┌ Guess the number ────────────────────────┐",
+            "│Still possible: 21 to 49, of 1 to 100     │",
+            "│░░░░░░░░█████████████░░░░░░░░░░░░░░░░░░░░░│",
+            "└──────────────────────────────────────────┘",
+            "3 attempt(s) left                           ",
+            "┌ Guesses ─────────────────────────────────┐",
+            "│    20  too small                         │",
+            "│    50  too big                           │",
+            "│                                          │",
+            "│                                          │",
+            "└──────────────────────────────────────────┘",
+            "┌ Hints ───────────────────────────────────┐",
+            "│The number is even. (-10 points)          │",
+            "└──────────────────────────────────────────┘",
+            "You guessed: 20 Too small!                  ",
+            "Guess: _                                    ",
+        ]);
+    }
+
+    #[test]
+    fn result_screen() {
+        let mut app = app(&[]);
+        for guess in &["1", "2", "3", "4"] {
+            type_line(&mut app, guess);
+        }
+        type_line(&mut app, "abc");
+        assert!(screen(&app).contains(&format!("{:<44}", "'abc' is not a number.")));
+
+        type_line(&mut app, "5");
+        let screen = screen(&app);
+        assert_eq!(format!("{:<44}", "You lose! The secret number was 42."), screen[14]);
+        assert_eq!(format!("{:<44}", "Press any key to quit."), screen[15]);
+
+        assert!(!app.is_done());
+        app.key(KeyEvent::from(KeyCode::Char('x')));
+        assert!(app.is_done());
+    }
+
+    #[test]
+    fn escape_quits() {
+        let mut app = app(&[]);
+        app.key(KeyEvent::from(KeyCode::Esc));
+        assert!(app.is_done());
+        assert_eq!(0, app.number().game().attempts());
+    }
+}
@@ -0,0 +1,158 @@
+extern crate guessing_game;
+
+// Integration Tests for serve and client: real sockets on localhost.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use guessing_game::Rules;
+use guessing_game::secret::ListSource;
+use guessing_game::server::{self, Lobby};
+
+/// Starts a server on a free port, with the secrets taken from `secrets`.
+fn start_server(secrets: Vec<u32>, max_attempts: Option<u32>) -> SocketAddr {
+    let rules = Rules { min: 1, max: 10, max_attempts };
+    let lobby = Lobby::new(rules, ListSource::new(secrets).unwrap()).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || server::serve(listener, lobby));
+    addr
+}
+
+/// A hand-driven client that speaks the raw protocol.
+struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Client {
+    fn connect(addr: SocketAddr) -> Client {
+        let stream = TcpStream::connect(addr).unwrap();
+        // Fail the test instead of hanging when a line never comes.
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        Client { reader: BufReader::new(stream.try_clone().unwrap()), writer: stream }
+    }
+
+    fn send(&mut self, line: &str) {
+        writeln!(self.writer, "{}", line).unwrap();
+    }
+
+    fn receive(&mut self) -> String {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        line.trim_end().to_string()
+    }
+
+    fn ask(&mut self, line: &str) -> String {
+        self.send(line);
+        self.receive()
+    }
+
+    fn receive_welcome(&mut self, name: &str) {
+        assert!(self.ask(&format!("JOIN {}", name)).starts_with("WELCOME"));
+    }
+}
+
+#[test]
+fn first_correct_guess_wins_for_everyone() {
+    let addr = start_server(vec![4, 9], None);
+    let mut ada = Client::connect(addr);
+    let mut bob = Client::connect(addr);
+
+    assert_eq!("WELCOME ada 1 1 10", ada.ask("JOIN ada"));
+    assert_eq!("WELCOME bob 1 1 10", bob.ask("JOIN bob"));
+    assert_eq!("ERROR JOIN first", Client::connect(addr).ask("GUESS 3"));
+
+    assert_eq!("SMALL", ada.ask("GUESS 2"));
+    assert_eq!("BIG", bob.ask("GUESS 6"));
+    assert_eq!("RANGE 1 10", bob.ask("GUESS 11"));
+
+    // Bob wins, ada hears about it without asking.
+    assert_eq!("WIN", bob.ask("GUESS 4"));
+    assert_eq!("ROUND 2 1 10", bob.receive());
+    assert_eq!("WINNER bob 4", ada.receive());
+    assert_eq!("ROUND 2 1 10", ada.receive());
+
+    // The second round has a new secret.
+    assert_eq!("SMALL", ada.ask("GUESS 4"));
+    assert_eq!("WIN", ada.ask("GUESS 9"));
+    assert_eq!("ROUND 3 1 10", ada.receive());
+    assert_eq!("WINNER ada 9", bob.receive());
+    assert_eq!("ROUND 3 1 10", bob.receive());
+
+    assert_eq!("LEADERBOARD ada=1 bob=1", bob.ask("LEADERBOARD"));
+    assert_eq!("BYE", ada.ask("QUIT"));
+}
+
+#[test]
+fn guesses_race_for_the_win() {
+    let addr = start_server(vec![7, 3], None);
+    let mut clients: Vec<Client> = (0..4).map(|i| {
+        let mut client = Client::connect(addr);
+        client.receive_welcome(&format!("p{}", i));
+        client
+    }).collect();
+
+    // Everyone sends the right answer at once. Exactly one of them wins.
+    for client in &mut clients {
+        client.send("GUESS 7");
+    }
+    let mut winners = 0;
+    for client in &mut clients {
+        match &client.receive()[..] {
+            "WIN" => winners += 1,
+            // Too late: the round was won, then this guess was too big
+            // for the next secret.
+            line => assert!(line.starts_with("WINNER") || line == "BIG", "{}", line),
+        }
+    }
+    assert_eq!(1, winners);
+}
+
+#[test]
+fn out_of_attempts() {
+    let addr = start_server(vec![5, 6], Some(1));
+    let mut ada = Client::connect(addr);
+    let mut bob = Client::connect(addr);
+    ada.ask("JOIN ada");
+    bob.ask("JOIN bob");
+    assert_eq!("LOST", ada.ask("GUESS 1"));
+    assert!(ada.ask("GUESS 5").starts_with("ERROR"));
+
+    // Bob was the last one still in the round.
+    assert_eq!("LOST", bob.ask("GUESS 1"));
+    for client in [&mut ada, &mut bob] {
+        assert_eq!("NOWINNER 5", client.receive());
+        assert_eq!("ROUND 2 1 10", client.receive());
+    }
+    assert_eq!("WIN", ada.ask("GUESS 6"));
+}
+
+#[test]
+fn bundled_client() {
+    let addr = start_server(vec![5, 7], None);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_guessing_game"))
+        .args(["client", "--port", &addr.port().to_string(), "--name", "cyd"])
+        .env("LANG", "C")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"3\nfive\n5\nleaderboard\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!("\
+Welcome, cyd! Round 1: guess the number between 1 and 10.
+Too small!
+Server says: 'five' is not a number.
+You win the round!
+Round 2: guess the number between 1 and 10.
+Leaderboard: cyd 1
+Bye!
+", String::from_utf8(output.stdout).unwrap());
+    assert!(output.status.success());
+}
@@ -0,0 +1,387 @@
+extern crate guessing_game;
+
+// Integration Tests: run the real binary with piped guesses and check
+// everything it prints.
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use guessing_game::Rules;
+use guessing_game::secret;
+
+const SEED: u64 = 2015;
+
+/// A scores file of our own, so tests never touch the real leaderboard.
+fn scores_file(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.json", name))
+}
+
+/// Runs the binary with `args`, feeds it `input` and returns its
+/// stdout and exit status.
+fn run(args: &[&str], input: &str) -> (String, Option<i32>) {
+    run_with_scores(args, input, &scores_file("transcript"))
+}
+
+fn run_with_scores(args: &[&str], input: &str, scores: &PathBuf) -> (String, Option<i32>) {
+    run_command(binary(args, scores), input)
+}
+
+/// The binary with `args` and its own leaderboard, in an English locale
+/// whatever the locale of the tests is.
+fn binary(args: &[&str], scores: &PathBuf) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_guessing_game"));
+    command.args(args)
+        .env("GUESSING_GAME_SCORES", scores)
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .env("LANG", "C");
+    command
+}
+
+fn run_command(mut command: Command, input: &str) -> (String, Option<i32>) {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to start guessing_game");
+
+    // The binary may quit before reading everything, so ignore errors.
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+
+    let output = child.wait_with_output().unwrap();
+    (String::from_utf8(output.stdout).unwrap(), output.status.code())
+}
+
+/// The secret the binary will pick for `SEED` and `rules`.
+fn secret_for(rules: &Rules) -> u32 {
+    secret::draw(&mut secret::seeded_rng(SEED), rules)
+}
+
+#[test]
+fn full_winning_transcript() {
+    let rules = Rules::default();
+    let secret = secret_for(&rules);
+    // The transcript below needs a neighbour on each side.
+    assert!(secret > rules.min && secret < rules.max);
+
+    let input = format!("{}\n{}\n{}\n", secret - 1, secret + 1, secret);
+    let (stdout, status) = run(&["--seed", "2015"], &input);
+
+    let expected = format!("\
+Guess the number!
+It is between 1 and 100.
+Type 'hint' to see the hints you can buy.
+Please input your guess.
+You guessed: {}
+Too small!
+Please input your guess.
+You guessed: {}
+Too big!
+Please input your guess.
+You guessed: {}
+You win!
+Score: 100 - 20 for 2 extra guess(es) - 0 for hints = 80 points.
+", secret - 1, secret + 1, secret);
+
+    assert_eq!(expected, stdout);
+    assert_eq!(Some(0), status);
+}
+
+#[test]
+fn losing_transcript_reveals_secret() {
+    let rules = Rules { min: 1, max: 10, max_attempts: Some(2) };
+    let secret = secret_for(&rules);
+    let wrong = if secret == 1 { 2 } else { 1 };
+
+    let input = format!("{}\n{}\n", wrong, wrong);
+    let (stdout, status) = run(&["--seed", "2015", "--max", "10", "--max-attempts", "2"],
+                               &input);
+
+    let feedback = if wrong < secret { "Too small!" } else { "Too big!" };
+    let expected = format!("\
+Guess the number!
+It is between 1 and 10.
+Type 'hint' to see the hints you can buy.
+Please input your guess.
+You guessed: {wrong}
+{feedback}
+1 attempt(s) left.
+Please input your guess.
+You guessed: {wrong}
+{feedback}
+You lose! The secret number was {secret}.
+Score: 0 points, a lost game scores nothing.
+", wrong = wrong, feedback = feedback, secret = secret);
+
+    assert_eq!(expected, stdout);
+    assert_eq!(Some(1), status);
+}
+
+#[test]
+fn bad_input_and_eof() {
+    let (stdout, status) = run(&["--seed", "2015"], "abc\n0\n");
+
+    assert_eq!("\
+Guess the number!
+It is between 1 and 100.
+Type 'hint' to see the hints you can buy.
+Please input your guess.
+'abc' is not a number.
+Please input your guess.
+You guessed: 0
+Out of range! Guess between 1 and 100.
+Please input your guess.
+No more input, quitting.
+", stdout);
+    assert_eq!(Some(3), status);
+}
+
+#[test]
+fn same_seed_same_game() {
+    let input = "10\n20\n30\n40\n50\n60\n70\n80\n90\n";
+    let first = run(&["--seed", "99"], input);
+    let second = run(&["--seed", "99"], input);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn bad_command_line() {
+    let (stdout, status) = run(&["--min", "10", "--max", "5"], "");
+    assert!(stdout.starts_with("error: --min (10) must not be bigger than --max (5)"));
+    assert_eq!(Some(2), status);
+}
+
+#[test]
+fn difficulty_and_hints() {
+    let input = "hint\nhint divisible\nhint parity\n600\nhint warmer\n300\nhint warmer\n";
+    let (stdout, _) = run(&["--difficulty", "hard", "--secret", "210"], input);
+
+    assert_eq!("\
+Guess the number!
+It is between 1 and 1000.
+Type 'hint' to see the hints you can buy.
+Please input your guess.
+Hints: hint warmer (-5)
+Please input your guess.
+The divisible hint is not allowed at this difficulty.
+Please input your guess.
+The parity hint is not allowed at this difficulty.
+Please input your guess.
+You guessed: 600
+Too big!
+11 attempt(s) left.
+Please input your guess.
+The warmer hint compares your last two guesses, make two first.
+Please input your guess.
+You guessed: 300
+Too big!
+10 attempt(s) left.
+Please input your guess.
+Warmer: your last guess is closer than the one before. (-5 points)
+Please input your guess.
+No more input, quitting.
+", stdout);
+
+    let input = "hint near\n5\n";
+    let (stdout, status) = run(&["--difficulty", "easy", "--secret", "5"], input);
+    assert!(stdout.contains("\
+The near hint is about your last guess, make one first.
+Please input your guess.
+You guessed: 5
+You win!
+Score: 100 - 0 for 0 extra guess(es) - 0 for hints = 100 points.
+"), "{}", stdout);
+    assert_eq!(Some(0), status);
+}
+
+#[test]
+fn reverse_mode_catches_cheating() {
+    let (stdout, status) = run(&["reverse", "--max", "4"], "higher\nmaybe\nlower\n");
+
+    assert_eq!("\
+Think of a number between 1 and 4, and I will guess it.
+Answer each guess with higher, lower or correct.
+My guess is 2.
+My guess is 3.
+Please answer higher, lower or correct.
+My guess is 3.
+You cheated: you said your number is higher than 2 and lower than 3, \
+but there is no number in between.
+", stdout);
+    assert_eq!(Some(1), status);
+}
+
+#[test]
+fn finished_games_reach_the_leaderboard() {
+    let scores = scores_file("leaderboard");
+    let _ = std::fs::remove_file(&scores);
+
+    let (_, status) = run_with_scores(&["--secret", "5", "--max", "10", "--name", "ada"],
+                                      "1\n5\n", &scores);
+    assert_eq!(Some(0), status);
+    let (_, status) = run_with_scores(&["--secret", "5", "--max", "10", "--name", "bob"],
+                                      "5\n", &scores);
+    assert_eq!(Some(0), status);
+    // Losses are saved, but never make the top list.
+    let (_, status) = run_with_scores(&["--secret", "5", "--max", "10", "--max-attempts", "1",
+                                        "--name", "cyd"], "1\n", &scores);
+    assert_eq!(Some(1), status);
+
+    let (stdout, status) = run_with_scores(&["scores", "--max", "10"], "", &scores);
+    assert_eq!(Some(0), status);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(3, lines.len(), "{}", stdout);
+    assert!(lines[1].contains("bob"));
+    assert!(lines[2].contains("ada"));
+
+    let (stdout, _) = run_with_scores(&["scores", "--max", "20"], "", &scores);
+    assert_eq!("No scores yet.\n", stdout);
+}
+
+#[test]
+fn adaptive_games_follow_the_history() {
+    let scores = scores_file("adaptive");
+    let _ = std::fs::remove_file(&scores);
+    let args = ["--adaptive", "--secret", "5", "--name", "ada"];
+
+    let (stdout, status) = run_with_scores(&args, "5\n", &scores);
+    assert!(stdout.starts_with("\
+No games played yet, so you start at 1 to 100 with 10 attempts.
+
+Guess the number!
+It is between 1 and 100.
+"), "{}", stdout);
+    assert_eq!(Some(0), status);
+
+    // Someone else's games don't count.
+    run_with_scores(&["--secret", "5", "--max", "10", "--name", "bob"], "5\n", &scores);
+
+    let (stdout, _) = run_with_scores(&args, "5\n", &scores);
+    assert!(stdout.starts_with("\
+You won 1 of your last 1 game(s), 100%. The aim is 70%.
+That is too few games to go by, so the range stays at 1 to 100.
+A perfect binary search needs 7 guesses for 1 to 100. 0 extra guess(es) were enough in 1 of \
+those games, so you get 7 attempts.
+"), "{}", stdout);
+}
+
+#[test]
+fn other_puzzles_share_the_loop() {
+    // Code number 1 is RRRG.
+    let (stdout, status) = run(&["mastermind", "--secret", "1", "--max-attempts", "2"],
+                               "GRRR\nRRRG\n");
+    assert!(stdout.ends_with("\
+Please input your guess.
+You guessed: GRRR
+Black: 2, white: 2
+1 attempt(s) left.
+Please input your guess.
+You guessed: RRRG
+You win!
+"), "{}", stdout);
+    assert_eq!(Some(0), status);
+
+    let words = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("words.txt");
+    fs::write(&words, "plate\ncrane\n").unwrap();
+    // The list is sorted, so word 1 is plate.
+    let (stdout, status) = run(&["words", "--secret", "1", "--max-attempts", "1",
+                                 "--words-file", words.to_str().unwrap()],
+                               "bread\ncrane\n");
+    assert!(stdout.ends_with("\
+Please input your guess.
+'bread' is not in the word list.
+Please input your guess.
+You guessed: crane
+             ..=.=
+You lose! The word was plate.
+"), "{}", stdout);
+    assert_eq!(Some(1), status);
+}
+
+#[test]
+fn record_and_replay() {
+    let recording = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("game.jsonl");
+    let recording = recording.to_str().unwrap();
+    let (_, status) = run(&["--secret", "42", "--record", recording], "50\nhint parity\n42\n");
+    assert_eq!(Some(0), status);
+
+    let (stdout, status) = run(&["replay", recording, "--speed", "0"], "");
+    assert!(stdout.contains("\
+> 50
+You guessed: 50
+Too big!
+Please input your guess.
+> hint parity
+The number is even. (-10 points)
+"), "{}", stdout);
+    assert!(stdout.ends_with("The replay matches the recording, the game is won.\n"), "{}", stdout);
+    assert_eq!(Some(0), status);
+
+    // Pretend the game answered something else back then.
+    let text = fs::read_to_string(recording).unwrap();
+    fs::write(recording, text.replace("TooBig", "TooSmall")).unwrap();
+    let (stdout, status) = run(&["replay", recording, "--speed", "0"], "");
+    assert!(stdout.ends_with("\
+The replay differs: line 4: the recording says 50 is too small, but the game now says 50 is too big
+"), "{}", stdout);
+    assert_eq!(Some(1), status);
+}
+
+#[test]
+fn time_attack_ends_while_waiting_for_input() {
+    let mut child = binary(&["time-attack", "--seconds", "1", "--secret", "42"],
+                           &scores_file("time_attack"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Keep stdin open: the game has to end on its own, with the reader
+    // still blocked.
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"50\n").unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.ends_with("\
+Time's up! The secret number was 42.
+Score: 0 points, a lost game scores nothing.
+"), "{}", stdout);
+    assert_eq!(Some(1), output.status.code());
+    drop(stdin);
+
+    let (stdout, status) = run(&["time-attack", "--seconds", "60", "--secret", "42"], "42\n");
+    assert!(stdout.contains("You have 60 second(s).\n"), "{}", stdout);
+    assert!(stdout.contains("You win!\nScore: 100 - 0 for 0 extra guess(es) + "), "{}", stdout);
+    assert_eq!(Some(0), status);
+}
+
+#[test]
+fn other_languages() {
+    let (stdout, status) = run(&["--lang", "de", "--secret", "5", "--max", "10"], "abc\n7\n5\n");
+    assert_eq!("\
+Errate die Zahl!
+Sie liegt zwischen 1 und 10.
+Tippe 'hint', um die Hinweise zu sehen, die du kaufen kannst.
+Bitte gib deinen Tipp ein.
+'abc' ist keine Zahl.
+Bitte gib deinen Tipp ein.
+Dein Tipp: 7
+Zu groß!
+Bitte gib deinen Tipp ein.
+Dein Tipp: 5
+Gewonnen!
+", &stdout[..stdout.find("Punkte").unwrap()]);
+    assert_eq!(Some(0), status);
+
+    // Without --lang the locale decides.
+    let mut french = binary(&["--secret", "5", "--max", "10", "--max-attempts", "1"],
+                            &scores_file("transcript"));
+    french.env("LANG", "fr_FR.UTF-8");
+    let (stdout, status) = run_command(french, "3\n");
+    assert!(stdout.starts_with("Devinez le nombre !\n"), "{}", stdout);
+    assert!(stdout.contains("Perdu ! Le nombre secret était 5.\n"), "{}", stdout);
+    assert_eq!(Some(1), status);
+}